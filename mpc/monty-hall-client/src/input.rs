@@ -0,0 +1,57 @@
+use ark_ff::UniformRand as _;
+use ark_serialize::CanonicalSerialize as _;
+use crypto_box::aead::OsRng;
+use mpc_core::protocols::rep3;
+
+use crate::Opening;
+
+/// A committed player input, ready to be sent to the MPC network.
+#[derive(Debug, Clone)]
+pub struct PlayerInput {
+    /// The sealed boxes for the nodes, in the order of the nodes.
+    pub ciphertexts: [Vec<u8>; 3],
+    /// The public commitment to the input, e.g., `door_c`.
+    pub commitment: ark_bn254::Fr,
+    /// What the player needs to keep to later prove what they committed to.
+    pub opening: Opening<2>,
+}
+
+/// Commits to `value` with fresh randomness (`CommitMetaData<2>`) and
+/// encrypts the rep3 shares of the value and the randomness for the nodes.
+///
+/// Node `i` receives its share of the value and its shares of the randomness
+/// in a sealed box for `node_public_keys[i]`, which is what the nodes expect
+/// in `door_ciphertext` and `switch_ciphertext`.
+pub fn share_input(
+    value: ark_bn254::Fr,
+    node_public_keys: &[crypto_box::PublicKey; 3],
+) -> eyre::Result<PlayerInput> {
+    let mut rng = OsRng;
+    let opening = Opening {
+        value,
+        randomness: [ark_bn254::Fr::rand(&mut rng), ark_bn254::Fr::rand(&mut rng)],
+    };
+    let value_shares = rep3::share_field_element(opening.value, &mut rng);
+    let [r0_shares, r1_shares] = opening
+        .randomness
+        .map(|r| rep3::share_field_element(r, &mut rng));
+
+    let mut ciphertexts: [Vec<u8>; 3] = Default::default();
+    for (i, (ciphertext, public_key)) in ciphertexts.iter_mut().zip(node_public_keys).enumerate() {
+        let share = (
+            value_shares[i].to_owned(),
+            [r0_shares[i].to_owned(), r1_shares[i].to_owned()],
+        );
+        let mut plaintext = Vec::new();
+        share.serialize_compressed(&mut plaintext)?;
+        *ciphertext = public_key
+            .seal(&mut rng, &plaintext)
+            .map_err(|_| eyre::eyre!("cannot encrypt share for node {i}"))?;
+    }
+
+    Ok(PlayerInput {
+        ciphertexts,
+        commitment: opening.commitment(),
+        opening,
+    })
+}
@@ -0,0 +1,42 @@
+//! The player side of the monty-hall game.
+//!
+//! The player never hands a plain input to anyone. Instead the client
+//!
+//! 1. commits to the input the same way `PrivateState::commit` of `pss_utils`
+//!    does,
+//! 2. rep3-shares the input together with the commitment randomness and
+//!    encrypts every share for the node that receives it,
+//! 3. verifies the proofs the MPC network returns, and
+//! 4. decrypts and combines the shares of the outputs meant for the player,
+//!    checking them against their public commitments.
+//!
+//! Everything is plain Rust, so the same code runs natively and, with the
+//! `wasm` feature, in the browser.
+
+mod input;
+mod output;
+mod verify;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+pub use input::{PlayerInput, share_input};
+pub use output::open_output;
+pub use pss_utils::{commit, poseidon2_hash};
+pub use verify::ProofVerifier;
+
+pub use crypto_box;
+
+/// A committed value together with the randomness that opens its
+/// commitment, i.e., the `data` and `meta` of a `PrivateState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opening<const N: usize> {
+    pub value: ark_bn254::Fr,
+    pub randomness: [ark_bn254::Fr; N],
+}
+
+impl<const N: usize> Opening<N> {
+    /// The commitment to this opening.
+    pub fn commitment(&self) -> ark_bn254::Fr {
+        commit(&[self.value], &self.randomness)
+    }
+}
@@ -0,0 +1,39 @@
+use ark_serialize::CanonicalDeserialize as _;
+use mpc_core::protocols::rep3::{self, Rep3PrimeFieldShare};
+
+use crate::Opening;
+
+type OutputShare = (
+    Rep3PrimeFieldShare<ark_bn254::Fr>,
+    Rep3PrimeFieldShare<ark_bn254::Fr>,
+);
+
+/// Decrypts the shares of a committed output the nodes sealed for the
+/// player, e.g., the opened door, and combines them.
+///
+/// The result is checked against the public `commitment`, so a node handing
+/// out a wrong share is detected.
+pub fn open_output(
+    secret_key: &crypto_box::SecretKey,
+    ciphertexts: &[Vec<u8>; 3],
+    commitment: ark_bn254::Fr,
+) -> eyre::Result<Opening<1>> {
+    let mut shares = Vec::with_capacity(3);
+    for (i, ciphertext) in ciphertexts.iter().enumerate() {
+        let plaintext = secret_key
+            .unseal(ciphertext)
+            .map_err(|_| eyre::eyre!("cannot decrypt share of node {i}"))?;
+        shares.push(OutputShare::deserialize_compressed(plaintext.as_slice())?);
+    }
+    let [(value0, r0), (value1, r1), (value2, r2)]: [OutputShare; 3] =
+        shares.try_into().expect("three ciphertexts");
+
+    let opening = Opening {
+        value: rep3::combine_field_element(value0, value1, value2),
+        randomness: [rep3::combine_field_element(r0, r1, r2)],
+    };
+    if opening.commitment() != commitment {
+        eyre::bail!("decrypted output does not match its commitment");
+    }
+    Ok(opening)
+}
@@ -0,0 +1,38 @@
+use co_builder::prelude::ZeroKnowledge;
+use co_noir::{Bn254, Poseidon2Sponge, UltraHonk, VerifyingKey, VerifyingKeyBarretenberg};
+use ultrahonk::prelude::HonkProof;
+
+/// Verifies the proofs of the MPC network, so the player does not have to
+/// trust the orchestration (or the chain) that they were checked.
+pub struct ProofVerifier {
+    crs: ark_bn254::G2Affine,
+}
+
+impl ProofVerifier {
+    /// Creates a verifier from the G2 point of the CRS.
+    pub fn new(crs: ark_bn254::G2Affine) -> Self {
+        Self { crs }
+    }
+
+    /// Reads the G2 point from the verifier CRS file of Barretenberg.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_crs_file(path: impl AsRef<std::path::Path>) -> eyre::Result<Self> {
+        Ok(Self::new(co_noir::CrsParser::<Bn254>::get_crs_g2(
+            path.as_ref(),
+        )?))
+    }
+
+    /// Verifies `proof` against the Barretenberg verification key `vk` of
+    /// the circuit it claims to be a proof for.
+    pub fn verify(&self, vk: &[u8], proof: &[u8]) -> eyre::Result<bool> {
+        let vk = VerifyingKeyBarretenberg::<Bn254>::from_buffer(vk)?;
+        let vk = VerifyingKey::from_barrettenberg_and_crs(vk, self.crs);
+        let proof = HonkProof::from_buffer(proof)?;
+        // the nodes prove without zero knowledge
+        Ok(UltraHonk::<_, Poseidon2Sponge>::verify(
+            proof,
+            vk,
+            ZeroKnowledge::No,
+        )?)
+    }
+}
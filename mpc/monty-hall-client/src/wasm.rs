@@ -0,0 +1,138 @@
+//! JavaScript bindings. Mirrors the JSON of the orchestration: field
+//! elements are decimal strings, keys and ciphertexts are hex strings.
+
+use std::str::FromStr as _;
+
+use ark_serialize::CanonicalDeserialize as _;
+use crypto_box::aead::OsRng;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{Opening, ProofVerifier};
+
+#[derive(Serialize)]
+struct PlayerKey {
+    secret_key: String,
+    public_key: String,
+}
+
+#[derive(Serialize)]
+struct PlayerInput {
+    ciphertexts: [String; 3],
+    commitment: String,
+    value: String,
+    randomness: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Output {
+    value: String,
+    randomness: Vec<String>,
+}
+
+fn to_js_error(err: eyre::Report) -> JsError {
+    JsError::new(&format!("{err:#}"))
+}
+
+fn parse_hex(value: &str) -> eyre::Result<Vec<u8>> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value))
+        .map_err(|_| eyre::eyre!("{value} is not a hex string"))
+}
+
+fn parse_field(value: &str) -> eyre::Result<ark_bn254::Fr> {
+    ark_bn254::Fr::from_str(value).map_err(|_| eyre::eyre!("{value} is not a field element"))
+}
+
+fn parse_node_public_keys(keys: Vec<String>) -> eyre::Result<[crypto_box::PublicKey; 3]> {
+    let keys = keys
+        .iter()
+        .map(|key| {
+            crypto_box::PublicKey::from_slice(&parse_hex(key)?)
+                .map_err(|_| eyre::eyre!("{key} is not a public key"))
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+    keys.try_into()
+        .map_err(|keys: Vec<_>| eyre::eyre!("expected 3 node public keys but got {}", keys.len()))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, JsError> {
+    serde_json::to_string(value).map_err(|err| JsError::new(&err.to_string()))
+}
+
+fn share(value: ark_bn254::Fr, node_public_keys: Vec<String>) -> Result<String, JsError> {
+    let node_public_keys = parse_node_public_keys(node_public_keys).map_err(to_js_error)?;
+    let input = crate::share_input(value, &node_public_keys).map_err(to_js_error)?;
+    to_json(&PlayerInput {
+        ciphertexts: input.ciphertexts.map(hex::encode),
+        commitment: input.commitment.to_string(),
+        value: input.opening.value.to_string(),
+        randomness: input
+            .opening
+            .randomness
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+    })
+}
+
+/// Generates the key pair the nodes encrypt the outputs for the player with.
+#[wasm_bindgen(js_name = generatePlayerKey)]
+pub fn generate_player_key() -> Result<String, JsError> {
+    let secret_key = crypto_box::SecretKey::generate(&mut OsRng);
+    to_json(&PlayerKey {
+        secret_key: hex::encode(secret_key.to_bytes()),
+        public_key: hex::encode(secret_key.public_key().as_bytes()),
+    })
+}
+
+/// Commits to and shares the door the player chooses, for `door_ciphertexts`
+/// and `door_c` of the reveal door request.
+#[wasm_bindgen(js_name = shareDoor)]
+pub fn share_door(door: u8, node_public_keys: Vec<String>) -> Result<String, JsError> {
+    share(ark_bn254::Fr::from(door), node_public_keys)
+}
+
+/// Commits to and shares the switch decision of the player, for
+/// `switch_ciphertexts` and `switch_c` of the finish game request.
+#[wasm_bindgen(js_name = shareSwitch)]
+pub fn share_switch(switch: bool, node_public_keys: Vec<String>) -> Result<String, JsError> {
+    share(ark_bn254::Fr::from(switch), node_public_keys)
+}
+
+/// Decrypts the shares of an output, e.g., `opened_door_ciphertexts`, and
+/// checks the result against its commitment.
+#[wasm_bindgen(js_name = openOutput)]
+pub fn open_output(
+    secret_key: &str,
+    ciphertexts: Vec<String>,
+    commitment: &str,
+) -> Result<String, JsError> {
+    let opening = (|| {
+        let secret_key = crypto_box::SecretKey::from_slice(&parse_hex(secret_key)?)
+            .map_err(|_| eyre::eyre!("invalid secret key"))?;
+        let ciphertexts = ciphertexts
+            .iter()
+            .map(|ciphertext| parse_hex(ciphertext))
+            .collect::<eyre::Result<Vec<_>>>()?
+            .try_into()
+            .map_err(|_| eyre::eyre!("expected 3 ciphertexts"))?;
+        crate::open_output(&secret_key, &ciphertexts, parse_field(commitment)?)
+    })()
+    .map_err(to_js_error)?;
+    let Opening { value, randomness } = opening;
+    to_json(&Output {
+        value: value.to_string(),
+        randomness: randomness.iter().map(ToString::to_string).collect(),
+    })
+}
+
+/// Verifies a proof of the MPC network. `crs` is the compressed G2 point of
+/// the CRS as hex string.
+#[wasm_bindgen(js_name = verifyProof)]
+pub fn verify_proof(crs: &str, vk: &[u8], proof: &[u8]) -> Result<bool, JsError> {
+    (|| {
+        let crs = ark_bn254::G2Affine::deserialize_compressed(parse_hex(crs)?.as_slice())?;
+        ProofVerifier::new(crs).verify(vk, proof)
+    })()
+    .map_err(to_js_error)
+}
@@ -0,0 +1,141 @@
+//! Access to the `MontyHall` contract.
+//!
+//! Each method of [`ChainClient`] maps to one entry point of the contract
+//! that the MPC network calls. The player-facing entry points
+//! (`choose_door`, `switch_door`) are not part of this trait, as they are
+//! called by the player directly.
+
+use ark_ff::{BigInteger as _, PrimeField as _};
+use eyre::Context as _;
+pub(crate) use pss_utils::poseidon2_hash;
+use serde::{Deserialize, Serialize};
+use tonic::async_trait;
+use uuid::Uuid;
+
+pub(crate) mod mock;
+pub(crate) mod pxe;
+
+pub(crate) use mock::MockChainClient;
+pub(crate) use pxe::PxeChainClient;
+
+/// The number of field elements of a verification key as the contract
+/// expects it in its capsule.
+pub(crate) const VK_SIZE: usize = 128;
+
+/// The public key of the MPC network the game states are nullified with.
+/// Mirrors `EmbeddedCurvePoint` of the Noir standard library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NullifyingPublicKey {
+    pub x: ark_bn254::Fr,
+    pub y: ark_bn254::Fr,
+    pub is_infinite: bool,
+}
+
+impl NullifyingPublicKey {
+    /// The packed representation of `EmbeddedCurvePoint`.
+    pub(crate) fn as_field_array(&self) -> [ark_bn254::Fr; 3] {
+        [self.x, self.y, ark_bn254::Fr::from(self.is_infinite)]
+    }
+}
+
+/// The entry points of the contract the MPC network calls, in the order a
+/// game runs through them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChainStep {
+    InitGame,
+    StartGame,
+    OpenDoor,
+    FinishGame,
+}
+
+/// The verification key and proof the contract pops from its capsules to
+/// verify a state transition.
+#[derive(Debug, Clone)]
+pub struct ProofCapsules {
+    pub vk: Vec<ark_bn254::Fr>,
+    pub proof: Vec<ark_bn254::Fr>,
+}
+
+#[async_trait]
+pub trait ChainClient: Send + Sync {
+    /// Calls `init_game`, committing to the seed of a new game, the hashes of
+    /// the verification keys of the init, choose and switch circuits and the
+    /// nullifying public key of the MPC network.
+    async fn init_game(
+        &self,
+        game_id: Uuid,
+        seed_c: ark_bn254::Fr,
+        vk_hashes: [ark_bn254::Fr; 3],
+        nullifying_pk: NullifyingPublicKey,
+    ) -> eyre::Result<()>;
+
+    /// Calls `start_game` with the commitment to the initial game state. The
+    /// capsules hold the proof of the init circuit.
+    async fn start_game(
+        &self,
+        game_id: Uuid,
+        game_state_c: ark_bn254::Fr,
+        capsules: ProofCapsules,
+    ) -> eyre::Result<()>;
+
+    /// Calls `open_door`, replacing the game state and publishing the
+    /// commitment to the opened door. The capsules hold the proof of the
+    /// choose circuit.
+    async fn open_door(
+        &self,
+        game_id: Uuid,
+        new_game_state_c: ark_bn254::Fr,
+        opened_door_c: ark_bn254::Fr,
+        game_state_nullifier: ark_bn254::Fr,
+        capsules: ProofCapsules,
+    ) -> eyre::Result<()>;
+
+    /// Calls `finish_game`, publishing the commitment to the outcome of the
+    /// game. The capsules hold the proof of the switch circuit.
+    async fn finish_game(
+        &self,
+        game_id: Uuid,
+        win_c: ark_bn254::Fr,
+        game_state_nullifier: ark_bn254::Fr,
+        capsules: ProofCapsules,
+    ) -> eyre::Result<()>;
+
+    /// The commitment `step` of `game_id` went on chain with, i.e., the seed
+    /// commitment of `init_game`, the new game state commitment of
+    /// `start_game` and `open_door` and the win commitment of `finish_game`.
+    /// `None` if the step is not on chain.
+    async fn step_commitment(
+        &self,
+        game_id: Uuid,
+        step: ChainStep,
+    ) -> eyre::Result<Option<ark_bn254::Fr>>;
+}
+
+/// Computes the hash the contract stores for a verification key.
+pub(crate) fn vk_hash(vk: &[ark_bn254::Fr]) -> ark_bn254::Fr {
+    poseidon2_hash(vk)
+}
+
+pub(crate) fn parse_hex_field(value: &str) -> eyre::Result<ark_bn254::Fr> {
+    let bytes = value.strip_prefix("0x").unwrap_or(value);
+    let bytes = (0..bytes.len())
+        .step_by(2)
+        .map(|i| {
+            bytes
+                .get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .with_context(|| format!("{value} is not a hex string"))
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+    Ok(ark_bn254::Fr::from_be_bytes_mod_order(&bytes))
+}
+
+pub(crate) fn to_hex_field(value: &ark_bn254::Fr) -> String {
+    let bytes = value.into_bigint().to_bytes_be();
+    let mut hex = String::with_capacity(2 + 2 * bytes.len());
+    hex.push_str("0x");
+    for byte in bytes {
+        hex.push_str(&format!("{byte:02x}"));
+    }
+    hex
+}
@@ -0,0 +1,169 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use tonic::async_trait;
+use uuid::Uuid;
+
+use super::{ChainClient, ChainStep, NullifyingPublicKey, ProofCapsules, VK_SIZE, vk_hash};
+
+/// The phases of the contract an in-memory game moves through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Initialized,
+    Started,
+    OpenedDoor,
+    Done,
+}
+
+struct MockGame {
+    vk_hashes: [ark_bn254::Fr; 3],
+    phase: Phase,
+    commitments: HashMap<ChainStep, ark_bn254::Fr>,
+}
+
+/// An in-memory stand-in for the `MontyHall` contract to run the
+/// orchestration without a PXE.
+///
+/// It checks what the contract checks apart from the proofs: the order of the
+/// calls, the verification keys against their hashes from `init_game` and
+/// that no game state is nullified twice. The proofs are already verified by
+/// the orchestration before they are sent to the chain.
+#[derive(Default)]
+pub(crate) struct MockChainClient {
+    games: Mutex<HashMap<Uuid, MockGame>>,
+    nullifiers: Mutex<HashSet<ark_bn254::Fr>>,
+}
+
+impl MockChainClient {
+    fn transition(
+        &self,
+        game_id: Uuid,
+        step: ChainStep,
+        commitment: ark_bn254::Fr,
+        capsules: &ProofCapsules,
+        game_state_nullifier: Option<ark_bn254::Fr>,
+    ) -> eyre::Result<()> {
+        let (from, to, vk_index) = match step {
+            ChainStep::InitGame => unreachable!("init_game creates the game"),
+            ChainStep::StartGame => (Phase::Initialized, Phase::Started, 0),
+            ChainStep::OpenDoor => (Phase::Started, Phase::OpenedDoor, 1),
+            ChainStep::FinishGame => (Phase::OpenedDoor, Phase::Done, 2),
+        };
+        let mut games = self.games.lock().expect("not poisoned");
+        let game = games
+            .get_mut(&game_id)
+            .ok_or_else(|| eyre::eyre!("game {game_id} is not initialized on chain"))?;
+        if game.phase != from {
+            eyre::bail!(
+                "game {game_id} is in phase {:?} but expected {from:?}",
+                game.phase
+            );
+        }
+        if capsules.vk.len() != VK_SIZE || vk_hash(&capsules.vk) != game.vk_hashes[vk_index] {
+            eyre::bail!("vk does not match vk hash {vk_index} of game {game_id}");
+        }
+        if capsules.proof.is_empty() {
+            eyre::bail!("missing proof for game {game_id}");
+        }
+        if let Some(nullifier) = game_state_nullifier {
+            if !self
+                .nullifiers
+                .lock()
+                .expect("not poisoned")
+                .insert(nullifier)
+            {
+                eyre::bail!("game state of game {game_id} is already nullified");
+            }
+        }
+        game.phase = to;
+        game.commitments.insert(step, commitment);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChainClient for MockChainClient {
+    async fn init_game(
+        &self,
+        game_id: Uuid,
+        seed_c: ark_bn254::Fr,
+        vk_hashes: [ark_bn254::Fr; 3],
+        nullifying_pk: NullifyingPublicKey,
+    ) -> eyre::Result<()> {
+        let mut games = self.games.lock().expect("not poisoned");
+        if games.contains_key(&game_id) {
+            eyre::bail!("game {game_id} is already initialized on chain");
+        }
+        tracing::info!("[mock chain] init_game {game_id}: seed_c {seed_c}, pk {nullifying_pk:?}");
+        games.insert(
+            game_id,
+            MockGame {
+                vk_hashes,
+                phase: Phase::Initialized,
+                commitments: HashMap::from([(ChainStep::InitGame, seed_c)]),
+            },
+        );
+        Ok(())
+    }
+
+    async fn start_game(
+        &self,
+        game_id: Uuid,
+        game_state_c: ark_bn254::Fr,
+        capsules: ProofCapsules,
+    ) -> eyre::Result<()> {
+        self.transition(game_id, ChainStep::StartGame, game_state_c, &capsules, None)?;
+        tracing::info!("[mock chain] start_game {game_id}: game_state_c {game_state_c}");
+        Ok(())
+    }
+
+    async fn open_door(
+        &self,
+        game_id: Uuid,
+        new_game_state_c: ark_bn254::Fr,
+        opened_door_c: ark_bn254::Fr,
+        game_state_nullifier: ark_bn254::Fr,
+        capsules: ProofCapsules,
+    ) -> eyre::Result<()> {
+        self.transition(
+            game_id,
+            ChainStep::OpenDoor,
+            new_game_state_c,
+            &capsules,
+            Some(game_state_nullifier),
+        )?;
+        tracing::info!(
+            "[mock chain] open_door {game_id}: game_state_c {new_game_state_c}, opened_door_c {opened_door_c}"
+        );
+        Ok(())
+    }
+
+    async fn finish_game(
+        &self,
+        game_id: Uuid,
+        win_c: ark_bn254::Fr,
+        game_state_nullifier: ark_bn254::Fr,
+        capsules: ProofCapsules,
+    ) -> eyre::Result<()> {
+        self.transition(
+            game_id,
+            ChainStep::FinishGame,
+            win_c,
+            &capsules,
+            Some(game_state_nullifier),
+        )?;
+        tracing::info!("[mock chain] finish_game {game_id}: win_c {win_c}");
+        Ok(())
+    }
+
+    async fn step_commitment(
+        &self,
+        game_id: Uuid,
+        step: ChainStep,
+    ) -> eyre::Result<Option<ark_bn254::Fr>> {
+        let games = self.games.lock().expect("not poisoned");
+        Ok(games
+            .get(&game_id)
+            .and_then(|game| game.commitments.get(&step).copied()))
+    }
+}
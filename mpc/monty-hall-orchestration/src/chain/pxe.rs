@@ -0,0 +1,476 @@
+//! A [`ChainClient`] talking to an Aztec PXE over its JSON-RPC interface.
+//!
+//! The contract functions are called through the default entrypoint, i.e.,
+//! the transaction originates from the contract itself and carries no fee
+//! payment. This works against a local sandbox; on a network with fees the
+//! contract has to be funded with Fee Juice first.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use ark_ff::PrimeField as _;
+use eyre::Context as _;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tonic::async_trait;
+use uuid::Uuid;
+
+use super::{
+    ChainClient, ChainStep, NullifyingPublicKey, ProofCapsules, parse_hex_field, poseidon2_hash,
+    to_hex_field,
+};
+
+/// `GENERATOR_INDEX__FUNCTION_ARGS` of the Aztec protocol constants.
+const GENERATOR_INDEX_FUNCTION_ARGS: u64 = 26;
+
+const DA_GAS_LIMIT: u64 = 1_000_000_000;
+const L2_GAS_LIMIT: u64 = 1_000_000_000;
+const TEARDOWN_DA_GAS_LIMIT: u64 = 12_000_000;
+const TEARDOWN_L2_GAS_LIMIT: u64 = 12_000_000;
+
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const RECEIPT_MAX_POLLS: usize = 300;
+
+/// The signatures of the contract functions the MPC network calls. The
+/// function selectors are derived from them.
+const INIT_GAME: &str = "init_game(Field,[Field;3],(Field,Field,bool))";
+const START_GAME: &str = "start_game(Field)";
+const OPEN_DOOR: &str = "open_door(Field,Field,Field)";
+const FINISH_GAME: &str = "finish_game(Field,Field)";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NodeInfo {
+    l1_chain_id: u64,
+    rollup_version: u64,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Deserialize, Debug)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+/// What the client knows of the contract, kept in a file across restarts.
+#[derive(Default, Serialize, Deserialize)]
+struct ContractState {
+    /// The game the contract hosts, once its `init_game` is mined.
+    game_id: Option<Uuid>,
+    /// The commitments of the mined steps, as hex fields.
+    mined: HashMap<ChainStep, String>,
+    /// The last transaction sent per step, which may not be mined yet.
+    sent: HashMap<ChainStep, SentTx>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct SentTx {
+    game_id: Uuid,
+    commitment: String,
+    tx_hash: String,
+}
+
+/// Drives a single deployed, not yet initialized `MontyHall` instance.
+///
+/// The contract holds the state of exactly one game, so the client binds the
+/// instance to the first game it initializes and rejects calls for any other
+/// game.
+///
+/// The contract keeps the commitments of the game private, so the client
+/// remembers the commitments of the steps it got mined to tell a retried step
+/// from a conflicting one. It also remembers the transactions it sent, such
+/// that a retried step waits for the transaction of the earlier attempt
+/// instead of sending a second one. Both are written to the state file
+/// before the client goes on, so a restarted orchestration does not submit
+/// a step again.
+pub(crate) struct PxeChainClient {
+    http: reqwest::Client,
+    url: String,
+    contract_address: ark_bn254::Fr,
+    node_info: NodeInfo,
+    state_path: PathBuf,
+    state: Mutex<ContractState>,
+    request_id: AtomicU64,
+}
+
+impl PxeChainClient {
+    pub(crate) async fn connect(
+        url: String,
+        contract_address: &str,
+        state_path: PathBuf,
+    ) -> eyre::Result<Self> {
+        let state = read_state(&state_path)
+            .with_context(|| format!("while reading PXE state at {}", state_path.display()))?;
+        let mut client = Self {
+            http: reqwest::Client::new(),
+            url,
+            contract_address: parse_hex_field(contract_address)
+                .context("while parsing contract address")?,
+            node_info: NodeInfo {
+                l1_chain_id: 0,
+                rollup_version: 0,
+            },
+            state_path,
+            state: Mutex::new(state),
+            request_id: AtomicU64::new(0),
+        };
+        client.node_info = client
+            .rpc("pxe_getNodeInfo", json!([]))
+            .await
+            .with_context(|| format!("while connecting to PXE at {}", client.url))?;
+        tracing::info!(
+            "connected to PXE at {} (chain id {}, rollup version {})",
+            client.url,
+            client.node_info.l1_chain_id,
+            client.node_info.rollup_version
+        );
+        Ok(client)
+    }
+
+    async fn rpc<T: DeserializeOwned>(&self, method: &str, params: Value) -> eyre::Result<T> {
+        let id = self.request_id.fetch_add(1, Ordering::Relaxed);
+        let response = self
+            .http
+            .post(&self.url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await
+            .with_context(|| format!("while sending {method}"))?
+            .error_for_status()?
+            .json::<JsonRpcResponse<T>>()
+            .await
+            .with_context(|| format!("while reading response of {method}"))?;
+        match (response.result, response.error) {
+            (_, Some(error)) => {
+                eyre::bail!("{method} failed with {}: {}", error.code, error.message)
+            }
+            (Some(result), None) => Ok(result),
+            // methods without a return value omit the result
+            (None, None) => serde_json::from_value(Value::Null)
+                .with_context(|| format!("{method} returned no result")),
+        }
+    }
+
+    fn check_game(&self, game_id: Uuid) -> eyre::Result<()> {
+        match self.state.lock().expect("not poisoned").game_id {
+            Some(bound) if bound == game_id => Ok(()),
+            Some(bound) => eyre::bail!(
+                "contract {} hosts game {bound}, not {game_id}",
+                to_hex_field(&self.contract_address)
+            ),
+            None => eyre::bail!("game {game_id} is not initialized on chain"),
+        }
+    }
+
+    /// Changes the state and writes it to the state file.
+    fn update_state(&self, update: impl FnOnce(&mut ContractState)) -> eyre::Result<()> {
+        let mut state = self.state.lock().expect("not poisoned");
+        update(&mut state);
+        write_state(&self.state_path, &state)
+            .with_context(|| format!("while writing PXE state to {}", self.state_path.display()))
+    }
+
+    /// Sends a transaction of `step` calling `signature` with `args` and
+    /// waits until it is mined. The capsules are pushed before, such that
+    /// the contract pops the verification key first and the proof second.
+    ///
+    /// If a transaction of an earlier attempt of the step with the same
+    /// commitment is known, it waits for that one instead and only sends a
+    /// new transaction if it is not mined.
+    async fn call(
+        &self,
+        step: ChainStep,
+        game_id: Uuid,
+        commitment: ark_bn254::Fr,
+        signature: &str,
+        args: Vec<ark_bn254::Fr>,
+        capsules: Option<ProofCapsules>,
+    ) -> eyre::Result<()> {
+        let commitment = to_hex_field(&commitment);
+        let sent = self
+            .state
+            .lock()
+            .expect("not poisoned")
+            .sent
+            .get(&step)
+            .filter(|sent| sent.game_id == game_id && sent.commitment == commitment)
+            .cloned();
+        if let Some(sent) = sent {
+            tracing::info!(
+                "waiting for {signature} sent before with tx hash {}",
+                sent.tx_hash
+            );
+            match self.wait_for_receipt(&sent.tx_hash).await {
+                Ok(()) => return self.mined(step, game_id, commitment),
+                Err(err) => tracing::warn!("sending {signature} again: {err:#}"),
+            }
+        }
+        if let Some(capsules) = capsules {
+            self.add_capsule(&capsules.proof).await?;
+            self.add_capsule(&capsules.vk).await?;
+        }
+        let tx_request = self.tx_request(signature, args);
+
+        tracing::debug!("simulating {signature}");
+        let simulation: Value = self
+            .rpc("pxe_simulateTx", json!([tx_request, true]))
+            .await?;
+        let private_execution_result = simulation
+            .get("privateExecutionResult")
+            .context("simulation did not return a private execution result")?;
+
+        tracing::debug!("proving {signature}");
+        let proving_result: Value = self
+            .rpc("pxe_proveTx", json!([tx_request, private_execution_result]))
+            .await?;
+        let tx = json!({
+            "data": proving_result.get("publicInputs"),
+            "clientIvcProof": proving_result.get("clientIvcProof"),
+            "contractClassLogPreimages": proving_result
+                .get("contractClassLogPreimages")
+                .cloned()
+                .unwrap_or_else(|| json!([])),
+            "publicFunctionCalldata": proving_result
+                .get("publicFunctionCalldata")
+                .cloned()
+                .unwrap_or_else(|| json!([])),
+        });
+
+        let tx_hash: String = self.rpc("pxe_sendTx", json!([tx])).await?;
+        tracing::info!("sent {signature} with tx hash {tx_hash}");
+        self.update_state(|state| {
+            state.sent.insert(
+                step,
+                SentTx {
+                    game_id,
+                    commitment: commitment.clone(),
+                    tx_hash: tx_hash.clone(),
+                },
+            );
+        })?;
+        self.wait_for_receipt(&tx_hash).await?;
+        self.mined(step, game_id, commitment)
+    }
+
+    /// Records that `step` of `game_id` is mined with `commitment`. Mining
+    /// `init_game` binds the contract to the game.
+    fn mined(&self, step: ChainStep, game_id: Uuid, commitment: String) -> eyre::Result<()> {
+        self.update_state(|state| {
+            if step == ChainStep::InitGame {
+                state.game_id = Some(game_id);
+            }
+            state.sent.remove(&step);
+            state.mined.insert(step, commitment);
+        })
+    }
+
+    async fn add_capsule(&self, capsule: &[ark_bn254::Fr]) -> eyre::Result<()> {
+        let capsule = capsule.iter().map(to_hex_field).collect::<Vec<_>>();
+        self.rpc::<Value>("pxe_addCapsule", json!([capsule]))
+            .await?;
+        Ok(())
+    }
+
+    async fn wait_for_receipt(&self, tx_hash: &str) -> eyre::Result<()> {
+        for _ in 0..RECEIPT_MAX_POLLS {
+            let receipt: Value = self.rpc("pxe_getTxReceipt", json!([tx_hash])).await?;
+            match receipt.get("status").and_then(Value::as_str) {
+                Some("success") => return Ok(()),
+                Some("pending") | None => tokio::time::sleep(RECEIPT_POLL_INTERVAL).await,
+                Some(status) => eyre::bail!(
+                    "tx {tx_hash} ended with status {status}: {}",
+                    receipt
+                        .get("error")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                ),
+            }
+        }
+        eyre::bail!("tx {tx_hash} was not mined in time")
+    }
+
+    /// Builds a `TxExecutionRequest` the way the default entrypoint of
+    /// aztec.js does.
+    fn tx_request(&self, signature: &str, args: Vec<ark_bn254::Fr>) -> Value {
+        let args_hash = args_hash(&args);
+        let origin = to_hex_field(&self.contract_address);
+        json!({
+            "origin": origin,
+            "functionSelector": function_selector(signature),
+            "firstCallArgsHash": to_hex_field(&args_hash),
+            "txContext": {
+                "chainId": to_hex_field(&ark_bn254::Fr::from(self.node_info.l1_chain_id)),
+                "version": to_hex_field(&ark_bn254::Fr::from(self.node_info.rollup_version)),
+                "gasSettings": {
+                    "gasLimits": { "daGas": DA_GAS_LIMIT, "l2Gas": L2_GAS_LIMIT },
+                    "teardownGasLimits": {
+                        "daGas": TEARDOWN_DA_GAS_LIMIT,
+                        "l2Gas": TEARDOWN_L2_GAS_LIMIT,
+                    },
+                    "maxFeesPerGas": { "feePerDaGas": "0x00", "feePerL2Gas": "0x00" },
+                    "maxPriorityFeesPerGas": { "feePerDaGas": "0x00", "feePerL2Gas": "0x00" },
+                },
+            },
+            "argsOfCalls": [{
+                "values": args.iter().map(to_hex_field).collect::<Vec<_>>(),
+                "hash": to_hex_field(&args_hash),
+            }],
+            "authWitnesses": [],
+        })
+    }
+}
+
+/// `computeVarArgsHash` of aztec.js.
+fn args_hash(args: &[ark_bn254::Fr]) -> ark_bn254::Fr {
+    if args.is_empty() {
+        return ark_bn254::Fr::from(0u64);
+    }
+    let mut inputs = Vec::with_capacity(args.len() + 1);
+    inputs.push(ark_bn254::Fr::from(GENERATOR_INDEX_FUNCTION_ARGS));
+    inputs.extend_from_slice(args);
+    poseidon2_hash(&inputs)
+}
+
+/// `FunctionSelector.fromSignature` of aztec.js: the last four bytes of the
+/// Poseidon2 hash of the signature, packed into fields of 31 bytes.
+fn function_selector(signature: &str) -> String {
+    let inputs = signature
+        .as_bytes()
+        .chunks(31)
+        .map(|chunk| {
+            let mut bytes = [0u8; 32];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            bytes.reverse();
+            ark_bn254::Fr::from_be_bytes_mod_order(&bytes)
+        })
+        .collect::<Vec<_>>();
+    let hash = to_hex_field(&poseidon2_hash(&inputs));
+    format!("0x{}", &hash[hash.len() - 8..])
+}
+
+#[async_trait]
+impl ChainClient for PxeChainClient {
+    async fn init_game(
+        &self,
+        game_id: Uuid,
+        seed_c: ark_bn254::Fr,
+        vk_hashes: [ark_bn254::Fr; 3],
+        nullifying_pk: NullifyingPublicKey,
+    ) -> eyre::Result<()> {
+        if let Some(bound) = self.state.lock().expect("not poisoned").game_id {
+            eyre::bail!(
+                "contract {} already hosts game {bound}",
+                to_hex_field(&self.contract_address)
+            );
+        }
+        let mut args = vec![seed_c];
+        args.extend(vk_hashes);
+        args.extend(nullifying_pk.as_field_array());
+        self.call(ChainStep::InitGame, game_id, seed_c, INIT_GAME, args, None)
+            .await
+    }
+
+    async fn start_game(
+        &self,
+        game_id: Uuid,
+        game_state_c: ark_bn254::Fr,
+        capsules: ProofCapsules,
+    ) -> eyre::Result<()> {
+        self.check_game(game_id)?;
+        self.call(
+            ChainStep::StartGame,
+            game_id,
+            game_state_c,
+            START_GAME,
+            vec![game_state_c],
+            Some(capsules),
+        )
+        .await
+    }
+
+    async fn open_door(
+        &self,
+        game_id: Uuid,
+        new_game_state_c: ark_bn254::Fr,
+        opened_door_c: ark_bn254::Fr,
+        game_state_nullifier: ark_bn254::Fr,
+        capsules: ProofCapsules,
+    ) -> eyre::Result<()> {
+        self.check_game(game_id)?;
+        self.call(
+            ChainStep::OpenDoor,
+            game_id,
+            new_game_state_c,
+            OPEN_DOOR,
+            vec![new_game_state_c, opened_door_c, game_state_nullifier],
+            Some(capsules),
+        )
+        .await
+    }
+
+    async fn finish_game(
+        &self,
+        game_id: Uuid,
+        win_c: ark_bn254::Fr,
+        game_state_nullifier: ark_bn254::Fr,
+        capsules: ProofCapsules,
+    ) -> eyre::Result<()> {
+        self.check_game(game_id)?;
+        self.call(
+            ChainStep::FinishGame,
+            game_id,
+            win_c,
+            FINISH_GAME,
+            vec![win_c, game_state_nullifier],
+            Some(capsules),
+        )
+        .await
+    }
+
+    async fn step_commitment(
+        &self,
+        game_id: Uuid,
+        step: ChainStep,
+    ) -> eyre::Result<Option<ark_bn254::Fr>> {
+        let state = self.state.lock().expect("not poisoned");
+        if state.game_id != Some(game_id) {
+            return Ok(None);
+        }
+        state
+            .mined
+            .get(&step)
+            .map(|commitment| parse_hex_field(commitment))
+            .transpose()
+    }
+}
+
+/// Reads the state file, a missing one is the state of a fresh contract.
+fn read_state(path: &Path) -> eyre::Result<ContractState> {
+    match std::fs::read(path) {
+        Ok(json) => Ok(serde_json::from_slice(&json)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(ContractState::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn write_state(path: &Path, state: &ContractState) -> eyre::Result<()> {
+    // a crash leaves either the old or the new state
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(state)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use std::{net::SocketAddr, path::PathBuf};
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
 #[derive(Parser)]
 pub struct ServerConfig {
@@ -8,15 +8,53 @@ pub struct ServerConfig {
     #[clap(long, env = "SMPC_BIND_ADDR")]
     pub bind_addr: SocketAddr,
 
-    /// The addresses of the mpc nodes
+    /// The addresses of the mpc nodes, in the order of their party ids
     #[clap(long, env = "SMPC_MPC_NODES", value_delimiter = ',')]
     pub mpc_nodes: Vec<String>,
 
-    /// Path to vk for init circuit
-    #[clap(long, env = "SMPC_INIT_CIRCUIT_VK", value_delimiter = ',')]
-    pub init_vk_path: PathBuf,
+    /// Path to the compiled init circuit, the vks are derived from the
+    /// circuits the way the nodes derive them
+    #[clap(long, env = "SMPC_INIT_CIRCUIT")]
+    pub init_circuit: PathBuf,
+
+    /// Path to the compiled choose circuit
+    #[clap(long, env = "SMPC_CHOOSE_CIRCUIT")]
+    pub choose_circuit: PathBuf,
+
+    /// Path to the compiled switch circuit
+    #[clap(long, env = "SMPC_SWITCH_CIRCUIT")]
+    pub switch_circuit: PathBuf,
+
+    /// Path to the prover crs
+    #[clap(long, env = "SMPC_CRS")]
+    pub crs_path: PathBuf,
 
     /// Path to the verifier crs
-    #[clap(long, env = "SMPC_VERIFIER_CRS", value_delimiter = ',')]
+    #[clap(long, env = "SMPC_VERIFIER_CRS")]
     pub verifier_crs: PathBuf,
+
+    /// The chain the orchestration sends the games to
+    #[clap(long, env = "SMPC_CHAIN", value_enum, default_value_t = Chain::Mock)]
+    pub chain: Chain,
+
+    /// The url of the PXE JSON-RPC interface (only for --chain pxe)
+    #[clap(long, env = "SMPC_PXE_URL", required_if_eq("chain", "pxe"))]
+    pub pxe_url: Option<String>,
+
+    /// The address of the deployed MontyHall contract (only for --chain pxe)
+    #[clap(long, env = "SMPC_CONTRACT_ADDRESS", required_if_eq("chain", "pxe"))]
+    pub contract_address: Option<String>,
+
+    /// The file the orchestration keeps the game and the sent steps of the
+    /// contract in across restarts (only for --chain pxe)
+    #[clap(long, env = "SMPC_PXE_STATE", required_if_eq("chain", "pxe"))]
+    pub pxe_state_path: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Chain {
+    /// An in-memory mock of the contract
+    Mock,
+    /// An Aztec PXE
+    Pxe,
 }
@@ -18,6 +18,12 @@ pub enum ApiErrors {
     Unauthorized,
     #[error("user sent a misformed request: \"{0}\"")]
     BadRequest(String),
+    #[error("mpc nodes disagree: {0}")]
+    NodesDiverged(String),
+    #[error("proof of the mpc nodes does not verify: {0}")]
+    InvalidProof(String),
+    #[error("mpc node {node} failed with {}: {}", .status.code(), .status.message())]
+    MpcNodeError { node: usize, status: tonic::Status },
     //#[error("generic wrapper for error that is already sent to user")]
     //ResponseError(Response),
     #[error(transparent)]
@@ -48,6 +54,23 @@ impl IntoResponse for ApiErrors {
                 StatusCode::INTERNAL_SERVER_ERROR.into_response()
             }
             ApiErrors::BadRequest(message) => (StatusCode::BAD_REQUEST, message).into_response(),
+            ApiErrors::NodesDiverged(message) | ApiErrors::InvalidProof(message) => {
+                tracing::error!("{message}");
+                ApiError::new(StatusCode::BAD_GATEWAY, message).into_response()
+            }
+            ApiErrors::MpcNodeError { node, status } => {
+                tracing::warn!("mpc node {node} failed: {status}");
+                let code = match status.code() {
+                    tonic::Code::InvalidArgument => StatusCode::BAD_REQUEST,
+                    tonic::Code::NotFound => StatusCode::NOT_FOUND,
+                    tonic::Code::FailedPrecondition => StatusCode::CONFLICT,
+                    tonic::Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+                    tonic::Code::Aborted => StatusCode::BAD_GATEWAY,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                ApiError::new(code, format!("mpc node {node}: {}", status.message()))
+                    .into_response()
+            }
             //ApiErrors::ResponseError(response) => response,
             ApiErrors::Unauthorized => (
                 StatusCode::UNAUTHORIZED,
@@ -0,0 +1,359 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::response::IntoResponse;
+use protos::monty_hall::{GamePhase, StageEvent, WatchJobRequest};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::{
+    error::{ApiErrors, ApiResult},
+    node_set::NodeSet,
+};
+
+/// How long a finished job can still be queried.
+const JOB_RETENTION: Duration = Duration::from_secs(15 * 60);
+/// How many events a slow subscriber may lag behind before it misses some.
+const EVENT_CAPACITY: usize = 64;
+/// How long to wait for the last stage events of the nodes after a step
+/// returned, such that a node that does not end its stream cannot hold up
+/// the step.
+const WATCH_GRACE: Duration = Duration::from_millis(200);
+
+/// The game steps a client can run as job.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    NewGame,
+    InitGame,
+    RevealDoor,
+    FinishGame,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// The stages of a job, in the order a step runs through them.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// The nodes sample the root randomness of a new game.
+    SampleRand,
+    /// The nodes generate the nullifying key of a new game.
+    GenerateNullifyingKey,
+    /// The nodes run the circuit of the step. They report its witness
+    /// extension, proving key and proof as [`JobEvent::NodeStage`].
+    Prove,
+    /// The orchestration verifies the proof of the nodes.
+    Verify,
+    /// The orchestration sends the step to the chain.
+    Chain,
+}
+
+/// The durations of a stage of a game step on every node, to spot the slow
+/// node.
+#[derive(Clone, Debug, Serialize)]
+pub struct NodeStages {
+    pub phase: String,
+    pub stage: String,
+    /// Per node, `None` while the node has not reported the stage.
+    pub durations_ms: Vec<Option<u64>>,
+    /// The node that took longest so far.
+    pub slowest_node: usize,
+}
+
+/// An event of a job. `elapsed_ms` counts from the creation of the job.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JobEvent {
+    Stage {
+        stage: Stage,
+        elapsed_ms: u64,
+    },
+    /// A node finished a stage of a game step, which took it `duration_ms`.
+    NodeStage {
+        phase: String,
+        stage: String,
+        node: usize,
+        duration_ms: u64,
+        elapsed_ms: u64,
+    },
+    Completed {
+        elapsed_ms: u64,
+        result: serde_json::Value,
+    },
+    Failed {
+        elapsed_ms: u64,
+        #[serde(flatten)]
+        error: JobError,
+    },
+}
+
+/// Why a job failed, with the status code the synchronous route would
+/// have answered with.
+#[derive(Clone, Debug, Serialize)]
+pub struct JobError {
+    pub code: u16,
+    pub message: String,
+}
+
+/// The state of a job as reported by `GET /jobs/{job_id}`.
+#[derive(Clone, Debug, Serialize)]
+pub struct JobInfo {
+    pub job_id: Uuid,
+    pub kind: JobKind,
+    pub game_id: Option<Uuid>,
+    pub status: JobStatus,
+    pub events: Vec<JobEvent>,
+    pub node_stages: Vec<NodeStages>,
+}
+
+struct Job {
+    info: JobInfo,
+    started: Instant,
+    tx: broadcast::Sender<JobEvent>,
+}
+
+/// The jobs of the orchestration. Jobs live in memory only, a restart of
+/// the orchestration forgets them.
+#[derive(Clone, Default)]
+pub struct Jobs {
+    jobs: Arc<Mutex<HashMap<Uuid, Job>>>,
+}
+
+/// Reports the progress of a step to its job, if the step runs as one.
+#[derive(Clone)]
+pub struct JobProgress {
+    job: Option<(Jobs, Uuid)>,
+}
+
+impl JobEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobEvent::Stage { .. } => "stage",
+            JobEvent::NodeStage { .. } => "node_stage",
+            JobEvent::Completed { .. } => "completed",
+            JobEvent::Failed { .. } => "failed",
+        }
+    }
+}
+
+impl JobError {
+    fn new(err: ApiErrors) -> Self {
+        // internal errors are logged but not shown, as by the routes
+        let message = match &err {
+            ApiErrors::InternalSeverError(_) => "internal server error".to_owned(),
+            err => err.to_string(),
+        };
+        let response = err.into_response();
+        Self {
+            code: response.status().as_u16(),
+            message,
+        }
+    }
+}
+
+impl Jobs {
+    /// Runs `step` as a new job and returns the id of the job.
+    pub fn spawn<T, F, Fut>(&self, kind: JobKind, game_id: Option<Uuid>, step: F) -> Uuid
+    where
+        T: Serialize + Send,
+        F: FnOnce(JobProgress) -> Fut,
+        Fut: Future<Output = ApiResult<T>> + Send + 'static,
+    {
+        let job_id = Uuid::new_v4();
+        let (tx, _) = broadcast::channel(EVENT_CAPACITY);
+        let job = Job {
+            info: JobInfo {
+                job_id,
+                kind,
+                game_id,
+                status: JobStatus::Running,
+                events: Vec::new(),
+                node_stages: Vec::new(),
+            },
+            started: Instant::now(),
+            tx,
+        };
+        self.jobs.lock().expect("not poisoned").insert(job_id, job);
+        let step = step(JobProgress {
+            job: Some((self.clone(), job_id)),
+        });
+        let jobs = self.clone();
+        tokio::spawn(async move {
+            let event = match step.await.and_then(|result| {
+                serde_json::to_value(result).map_err(|err| eyre::Report::from(err).into())
+            }) {
+                Ok(result) => jobs.finish(job_id, |elapsed_ms| JobEvent::Completed {
+                    elapsed_ms,
+                    result,
+                }),
+                Err(err) => {
+                    let error = JobError::new(err);
+                    jobs.finish(job_id, |elapsed_ms| JobEvent::Failed { elapsed_ms, error })
+                }
+            };
+            tracing::info!("{kind:?} job {job_id} {}", event.name());
+            tokio::time::sleep(JOB_RETENTION).await;
+            jobs.jobs.lock().expect("not poisoned").remove(&job_id);
+        });
+        job_id
+    }
+
+    pub fn info(&self, job_id: Uuid) -> Option<JobInfo> {
+        let jobs = self.jobs.lock().expect("not poisoned");
+        jobs.get(&job_id).map(|job| job.info.clone())
+    }
+
+    /// The events of a job so far together with a receiver of the events
+    /// to come, or `None` if there is no such job.
+    pub fn subscribe(
+        &self,
+        job_id: Uuid,
+    ) -> Option<(Vec<JobEvent>, Option<broadcast::Receiver<JobEvent>>)> {
+        let jobs = self.jobs.lock().expect("not poisoned");
+        let job = jobs.get(&job_id)?;
+        // a finished job has no events to come
+        let rx = (job.info.status == JobStatus::Running).then(|| job.tx.subscribe());
+        Some((job.info.events.clone(), rx))
+    }
+
+    fn push(&self, job_id: Uuid, event: impl FnOnce(u64) -> JobEvent) -> Option<JobEvent> {
+        let mut jobs = self.jobs.lock().expect("not poisoned");
+        let job = jobs.get_mut(&job_id)?;
+        let elapsed_ms = job.started.elapsed().as_millis() as u64;
+        let event = event(elapsed_ms);
+        match &event {
+            JobEvent::Stage { .. } | JobEvent::NodeStage { .. } => {}
+            JobEvent::Completed { .. } => job.info.status = JobStatus::Completed,
+            JobEvent::Failed { .. } => job.info.status = JobStatus::Failed,
+        }
+        job.info.events.push(event.clone());
+        // nobody may be listening
+        let _ = job.tx.send(event.clone());
+        Some(event)
+    }
+
+    /// Records the stage a node reported and updates the summary of the
+    /// stage across the nodes.
+    fn push_node_stage(
+        &self,
+        job_id: Uuid,
+        num_nodes: usize,
+        phase: GamePhase,
+        node: usize,
+        event: &StageEvent,
+    ) {
+        let phase = phase.as_str_name().to_lowercase();
+        let stage = event.stage().as_str_name().to_lowercase();
+        let summary = {
+            let mut jobs = self.jobs.lock().expect("not poisoned");
+            let Some(job) = jobs.get_mut(&job_id) else {
+                return;
+            };
+            let node_stages = &mut job.info.node_stages;
+            let index = match node_stages
+                .iter()
+                .position(|stages| stages.phase == phase && stages.stage == stage)
+            {
+                Some(index) => index,
+                None => {
+                    node_stages.push(NodeStages {
+                        phase: phase.clone(),
+                        stage: stage.clone(),
+                        durations_ms: vec![None; num_nodes],
+                        slowest_node: node,
+                    });
+                    node_stages.len() - 1
+                }
+            };
+            let stages = &mut node_stages[index];
+            stages.durations_ms[node] = Some(event.duration_ms);
+            if stages.durations_ms[stages.slowest_node] < Some(event.duration_ms) {
+                stages.slowest_node = node;
+            }
+            stages.clone()
+        };
+        if summary.durations_ms.iter().all(Option::is_some) {
+            tracing::info!(
+                "{} {} took {:?} ms on the nodes, node {} is the slowest",
+                summary.phase,
+                summary.stage,
+                summary.durations_ms.iter().flatten().collect::<Vec<_>>(),
+                summary.slowest_node
+            );
+        }
+        self.push(job_id, |elapsed_ms| JobEvent::NodeStage {
+            phase,
+            stage,
+            node,
+            duration_ms: event.duration_ms,
+            elapsed_ms,
+        });
+    }
+
+    fn finish(&self, job_id: Uuid, event: impl FnOnce(u64) -> JobEvent) -> JobEvent {
+        self.push(job_id, event)
+            .expect("jobs are removed after they finished")
+    }
+}
+
+impl JobProgress {
+    /// The progress of a step that does not run as job.
+    pub fn untracked() -> Self {
+        Self { job: None }
+    }
+
+    pub fn stage(&self, stage: Stage) {
+        if let Some((jobs, job_id)) = &self.job {
+            jobs.push(*job_id, |elapsed_ms| JobEvent::Stage { stage, elapsed_ms });
+        }
+    }
+
+    /// Runs a game step on the nodes while recording the stages the nodes
+    /// report for its job.
+    pub async fn watch_nodes<T>(
+        &self,
+        nodes: &NodeSet,
+        game_id: Uuid,
+        phase: GamePhase,
+        step: impl Future<Output = T>,
+    ) -> T {
+        let Some((jobs, job_id)) = &self.job else {
+            return step.await;
+        };
+        let mut request = WatchJobRequest {
+            game_id: game_id.to_string(),
+            ..Default::default()
+        };
+        request.set_phase(phase);
+        // the nodes watch the job once the streams are open, so open them
+        // before the step starts
+        let streams = nodes.watch_job(request).await;
+        let watch =
+            futures::future::join_all(streams.into_iter().map(|(node, mut stream)| async move {
+                // the stream ends with the job on the node
+                while let Ok(Some(event)) = stream.message().await {
+                    jobs.push_node_stage(*job_id, nodes.len(), phase, node, &event);
+                }
+            }));
+        tokio::pin!(step, watch);
+        let result = tokio::select! {
+            result = &mut step => result,
+            _ = &mut watch => return step.await,
+        };
+        // the nodes end the streams with the job, or right away when they
+        // answer from a stored response
+        let _ = tokio::time::timeout(WATCH_GRACE, watch).await;
+        result
+    }
+}
@@ -1,17 +1,23 @@
-use std::{path::PathBuf, sync::Arc};
+use std::sync::Arc;
 
 use axum::Router;
+use chain::{ChainClient, MockChainClient, PxeChainClient};
 use clap::{Args, Parser};
-use co_noir::{Bn254, CrsParser, VerifyingKey, VerifyingKeyBarretenberg};
-use config::ServerConfig;
+use config::{Chain, ServerConfig};
 use eyre::Context;
-use mpc_node::MpcNodeHandle;
+use jobs::Jobs;
+use node_set::NodeSet;
 use tower_http::cors::CorsLayer;
+use vks::CircuitVks;
 
+mod chain;
 mod config;
 mod error;
+mod jobs;
 mod mpc_node;
+mod node_set;
 mod routes;
+mod vks;
 
 fn install_tracing() {
     use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
@@ -25,11 +31,10 @@ fn install_tracing() {
 
 #[derive(Clone)]
 pub struct AppState {
-    pub node0: MpcNodeHandle,
-    pub node1: MpcNodeHandle,
-    pub node2: MpcNodeHandle,
-    pub verifier_crs: ark_bn254::G2Affine,
-    pub init_vk_path: PathBuf,
+    pub nodes: NodeSet,
+    pub vks: Arc<CircuitVks>,
+    pub chain: Arc<dyn ChainClient>,
+    pub jobs: Jobs,
 }
 
 #[tokio::main]
@@ -38,23 +43,33 @@ async fn main() -> eyre::Result<()> {
 
     let config = ServerConfig::parse();
 
-    let verifier_crs = CrsParser::<Bn254>::get_crs_g2(config.verifier_crs)?;
+    let vks = Arc::new(CircuitVks::load(&config)?);
 
-    let (node0, node1, node2) = tokio::join!(
-        mpc_node::connect(&config.mpc_nodes[0]),
-        mpc_node::connect(&config.mpc_nodes[1]),
-        mpc_node::connect(&config.mpc_nodes[2])
-    );
-    let node0 = node0?;
-    let node1 = node1?;
-    let node2 = node2?;
+    let nodes = NodeSet::connect(&config.mpc_nodes).await?;
+
+    let chain: Arc<dyn ChainClient> = match config.chain {
+        Chain::Mock => {
+            tracing::info!("using in-memory mock chain");
+            Arc::new(MockChainClient::default())
+        }
+        Chain::Pxe => Arc::new(
+            PxeChainClient::connect(
+                config.pxe_url.clone().expect("required by clap"),
+                config
+                    .contract_address
+                    .as_deref()
+                    .expect("required by clap"),
+                config.pxe_state_path.clone().expect("required by clap"),
+            )
+            .await?,
+        ),
+    };
 
     let app_state = AppState {
-        node0,
-        node1,
-        node2,
-        verifier_crs,
-        init_vk_path: config.init_vk_path.clone(),
+        nodes,
+        vks,
+        chain,
+        jobs: Jobs::default(),
     };
 
     let app = Router::new()
@@ -1,79 +1,134 @@
+use std::future::Future;
+
 use protos::monty_hall::{
-    InitGameRequest, InitGameResponse, RevealDoorResponse, SampleRandRequest, SampleRandResponse,
+    FinishGameRequest, FinishGameResponse, GenerateNullifyingKeyRequest,
+    GenerateNullifyingKeyResponse, GetPublicKeyRequest, GetPublicKeyResponse, InitGameRequest,
+    InitGameResponse, ListJobsRequest, ListJobsResponse, RefreshSharesRequest,
+    RefreshSharesResponse, RevealDoorRequest, RevealDoorResponse, RollbackJobRequest,
+    RollbackJobResponse, SampleRandRequest, SampleRandResponse, StageEvent, WatchJobRequest,
     mpc_node_service_client::MpcNodeServiceClient,
 };
-use tokio::sync::{mpsc, oneshot};
-
-type RootRand = oneshot::Sender<Result<SampleRandResponse, tonic::Status>>;
+use tonic::{IntoRequest, Streaming, transport::Channel};
 
-struct NewGame {
-    tx: oneshot::Sender<Result<InitGameResponse, tonic::Status>>,
-}
+use crate::error::{ApiErrors, ApiResult};
 
-enum MpcNodeJob {
-    RootRand(RootRand),
-    NewGame(NewGame),
-    RevealDoor(oneshot::Sender<Result<(), tonic::Status>>),
-}
+type Client = MpcNodeServiceClient<Channel>;
 
 #[derive(Clone, Debug)]
 pub struct MpcNodeHandle {
-    handle: mpsc::Sender<MpcNodeJob>,
+    id: usize,
+    client: Client,
 }
 
-pub(super) async fn connect(addr: &str) -> eyre::Result<MpcNodeHandle> {
-    let addr = addr.to_string();
-    let (tx, mut rx) = mpsc::channel(4);
-    let (finished_tx, finished_rx) = oneshot::channel();
-    tokio::spawn(async move {
-        let mut client = match MpcNodeServiceClient::connect(addr.to_string()).await {
-            Ok(client) => {
-                let _ = finished_tx.send(Ok(()));
-                client
-            }
-            Err(err) => {
-                let _ = finished_tx.send(Err(err));
-                return;
-            }
-        };
-        while let Some(job) = rx.recv().await {
-            match job {
-                MpcNodeJob::RootRand(tx) => {
-                    let result = client.sample_rand(SampleRandRequest {}).await;
-                    let _ = tx.send(result.map(|result| result.into_inner()));
-                }
-                MpcNodeJob::NewGame(new_game) => {
-                    let result = client.init_game(InitGameRequest {}).await;
-
-                    let _ = new_game.tx.send(result.map(|result| result.into_inner()));
-                }
-                MpcNodeJob::RevealDoor(sender) => todo!(),
-            }
-        }
-    });
-    finished_rx.await??;
-    Ok(MpcNodeHandle { handle: tx })
+pub(super) async fn connect(id: usize, addr: &str) -> eyre::Result<MpcNodeHandle> {
+    let client = MpcNodeServiceClient::connect(addr.to_string()).await?;
+    Ok(MpcNodeHandle { id, client })
 }
 
 impl MpcNodeHandle {
-    pub(crate) async fn sample_root_rand(&self) -> eyre::Result<SampleRandResponse> {
-        let (tx, rx) = oneshot::channel();
-        self.handle.send(MpcNodeJob::RootRand(tx)).await?;
-        rx.await.unwrap().map_err(|err| eyre::eyre!(err))
+    /// Calls `rpc` on a clone of the client. The clones share the
+    /// connection, so calls of independent games do not wait on each other
+    /// and the nodes run their MPC sessions in parallel.
+    async fn call<Req, Resp, F, Fut>(
+        &self,
+        request: impl IntoRequest<Req>,
+        rpc: F,
+    ) -> ApiResult<Resp>
+    where
+        F: FnOnce(Client, tonic::Request<Req>) -> Fut,
+        Fut: Future<Output = Result<tonic::Response<Resp>, tonic::Status>>,
+    {
+        rpc(self.client.clone(), request.into_request())
+            .await
+            .map(tonic::Response::into_inner)
+            .map_err(|status| ApiErrors::MpcNodeError {
+                node: self.id,
+                status,
+            })
+    }
+
+    pub(crate) async fn get_public_key(&self) -> ApiResult<GetPublicKeyResponse> {
+        self.call(GetPublicKeyRequest {}, |mut client, request| async move {
+            client.get_public_key(request).await
+        })
+        .await
+    }
+    pub(crate) async fn sample_root_rand(
+        &self,
+        request: tonic::Request<SampleRandRequest>,
+    ) -> ApiResult<SampleRandResponse> {
+        self.call(request, |mut client, request| async move {
+            client.sample_rand(request).await
+        })
+        .await
+    }
+    pub(crate) async fn generate_nullifying_key(
+        &self,
+        request: tonic::Request<GenerateNullifyingKeyRequest>,
+    ) -> ApiResult<GenerateNullifyingKeyResponse> {
+        self.call(request, |mut client, request| async move {
+            client.generate_nullifying_key(request).await
+        })
+        .await
+    }
+    pub(crate) async fn new_game(
+        &self,
+        request: tonic::Request<InitGameRequest>,
+    ) -> ApiResult<InitGameResponse> {
+        self.call(request, |mut client, request| async move {
+            client.init_game(request).await
+        })
+        .await
+    }
+    pub(crate) async fn reveal_door(
+        &self,
+        request: tonic::Request<RevealDoorRequest>,
+    ) -> ApiResult<RevealDoorResponse> {
+        self.call(request, |mut client, request| async move {
+            client.reveal_door(request).await
+        })
+        .await
+    }
+    pub(crate) async fn finish_game(
+        &self,
+        request: tonic::Request<FinishGameRequest>,
+    ) -> ApiResult<FinishGameResponse> {
+        self.call(request, |mut client, request| async move {
+            client.finish_game(request).await
+        })
+        .await
+    }
+    pub(crate) async fn refresh_shares(
+        &self,
+        request: tonic::Request<RefreshSharesRequest>,
+    ) -> ApiResult<RefreshSharesResponse> {
+        self.call(request, |mut client, request| async move {
+            client.refresh_shares(request).await
+        })
+        .await
+    }
+    pub(crate) async fn list_jobs(&self) -> ApiResult<ListJobsResponse> {
+        self.call(ListJobsRequest {}, |mut client, request| async move {
+            client.list_jobs(request).await
+        })
+        .await
     }
-    pub(crate) async fn new_game(&self) -> eyre::Result<InitGameResponse> {
-        let (tx, rx) = oneshot::channel();
-        self.handle
-            .send(MpcNodeJob::NewGame(NewGame { tx }))
-            .await?;
-        rx.await.unwrap().map_err(|err| eyre::eyre!(err))
+    pub(crate) async fn rollback_job(
+        &self,
+        request: RollbackJobRequest,
+    ) -> ApiResult<RollbackJobResponse> {
+        self.call(request, |mut client, request| async move {
+            client.rollback_job(request).await
+        })
+        .await
     }
-    pub(crate) async fn reveal_door(&self) -> eyre::Result<RevealDoorResponse> {
-        todo!()
-        //let (tx, rx) = oneshot::channel();
-        //self.handle
-        //    .send(MpcNodeJob::RevealDoor(Re { tx }))
-        //    .await?;
-        //rx.await.unwrap().map_err(|err| eyre::eyre!(err))
+    pub(crate) async fn watch_job(
+        &self,
+        request: WatchJobRequest,
+    ) -> ApiResult<Streaming<StageEvent>> {
+        self.call(request, |mut client, request| async move {
+            client.watch_job(request).await
+        })
+        .await
     }
 }
@@ -0,0 +1,251 @@
+use std::future::Future;
+
+use eyre::Context as _;
+use protos::monty_hall::{StageEvent, WatchJobRequest};
+use tonic::Streaming;
+use uuid::Uuid;
+
+use crate::error::{ApiErrors, ApiResult};
+use crate::mpc_node::{self, MpcNodeHandle};
+
+/// The MPC nodes of the network, in the order of their party ids.
+#[derive(Clone, Debug)]
+pub struct NodeSet {
+    nodes: Vec<MpcNodeHandle>,
+}
+
+impl NodeSet {
+    /// Connects to the nodes, which run rep3 and have to be three.
+    pub(crate) async fn connect(addrs: &[String]) -> eyre::Result<Self> {
+        if addrs.len() != 3 {
+            eyre::bail!("rep3 needs 3 mpc nodes but {} are configured", addrs.len());
+        }
+        let nodes =
+            futures::future::join_all(addrs.iter().enumerate().map(|(id, addr)| async move {
+                mpc_node::connect(id, addr)
+                    .await
+                    .with_context(|| format!("while connecting to mpc node {id} at {addr}"))
+            }))
+            .await
+            .into_iter()
+            .collect::<eyre::Result<Vec<_>>>()?;
+        Ok(Self { nodes })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Sends `requests[i]` to node `i` and collects the responses in the
+    /// same order.
+    ///
+    /// If a node refused the step because the game is in another phase, its
+    /// error is returned over the errors of the nodes that ran into the
+    /// aborted MPC, so out-of-order calls end up as 409 Conflict.
+    pub(crate) async fn fan_out<Req, Resp, F, Fut>(
+        &self,
+        requests: Vec<Req>,
+        call: F,
+    ) -> ApiResult<Vec<Resp>>
+    where
+        F: Fn(MpcNodeHandle, Req) -> Fut,
+        Fut: Future<Output = ApiResult<Resp>>,
+    {
+        if requests.len() != self.len() {
+            Err(eyre::eyre!(
+                "got {} requests for {} mpc nodes",
+                requests.len(),
+                self.len()
+            ))?;
+        }
+        let results = futures::future::join_all(
+            self.nodes
+                .iter()
+                .cloned()
+                .zip(requests)
+                .map(|(node, request)| call(node, request)),
+        )
+        .await;
+        let mut responses = Vec::with_capacity(results.len());
+        let mut first_err = None;
+        for result in results {
+            match result {
+                Ok(response) => responses.push(response),
+                Err(err) if is_conflict(&err) => return Err(err),
+                Err(err) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+        match first_err {
+            Some(err) => Err(err),
+            None => Ok(responses),
+        }
+    }
+
+    /// Opens a stream of the stage events of a job on every node, together
+    /// with the index of the node. Watching is best effort, a node that
+    /// cannot be watched is left out.
+    pub(crate) async fn watch_job(
+        &self,
+        request: WatchJobRequest,
+    ) -> Vec<(usize, Streaming<StageEvent>)> {
+        futures::future::join_all(self.nodes.iter().enumerate().map(|(id, node)| {
+            let request = request.clone();
+            async move {
+                match node.watch_job(request).await {
+                    Ok(stream) => Some((id, stream)),
+                    Err(err) => {
+                        tracing::warn!("cannot watch job on mpc node {id}: {err}");
+                        None
+                    }
+                }
+            }
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Sends the same request to all nodes.
+    pub(crate) async fn broadcast<Req, Resp, F, Fut>(
+        &self,
+        request: Req,
+        call: F,
+    ) -> ApiResult<Vec<Resp>>
+    where
+        Req: Clone,
+        F: Fn(MpcNodeHandle, Req) -> Fut,
+        Fut: Future<Output = ApiResult<Resp>>,
+    {
+        self.fan_out(vec![request; self.len()], call).await
+    }
+
+    /// Like [`Self::fan_out`] for requests running an MPC session. The
+    /// requests carry a fresh attempt id, the same for all nodes, which
+    /// keys the session on the nodes.
+    pub(crate) async fn fan_out_session<Req, Resp, F, Fut>(
+        &self,
+        requests: Vec<Req>,
+        call: F,
+    ) -> ApiResult<Vec<Resp>>
+    where
+        F: Fn(MpcNodeHandle, tonic::Request<Req>) -> Fut,
+        Fut: Future<Output = ApiResult<Resp>>,
+    {
+        let attempt = Uuid::new_v4()
+            .to_string()
+            .parse::<tonic::metadata::MetadataValue<_>>()
+            .expect("uuid is valid metadata");
+        let requests = requests
+            .into_iter()
+            .map(|request| {
+                let mut request = tonic::Request::new(request);
+                request
+                    .metadata_mut()
+                    .insert(protos::SESSION_ATTEMPT_KEY, attempt.clone());
+                request
+            })
+            .collect();
+        self.fan_out(requests, call).await
+    }
+
+    /// Like [`Self::broadcast`] for a request running an MPC session, see
+    /// [`Self::fan_out_session`].
+    pub(crate) async fn broadcast_session<Req, Resp, F, Fut>(
+        &self,
+        request: Req,
+        call: F,
+    ) -> ApiResult<Vec<Resp>>
+    where
+        Req: Clone,
+        F: Fn(MpcNodeHandle, tonic::Request<Req>) -> Fut,
+        Fut: Future<Output = ApiResult<Resp>>,
+    {
+        self.fan_out_session(vec![request; self.len()], call).await
+    }
+}
+
+fn is_conflict(err: &ApiErrors) -> bool {
+    matches!(
+        err,
+        ApiErrors::MpcNodeError { status, .. } if status.code() == tonic::Code::FailedPrecondition
+    )
+}
+
+/// Checks that all nodes answered the same and returns the common response.
+///
+/// The response of the majority counts as the right one, the nodes
+/// deviating from it are reported.
+pub(crate) fn agree<T: PartialEq>(mut responses: Vec<T>, what: &str) -> ApiResult<T> {
+    let reference = (0..responses.len())
+        .rev()
+        .max_by_key(|&i| {
+            responses
+                .iter()
+                .filter(|response| **response == responses[i])
+                .count()
+        })
+        .context("no responses from the mpc nodes")?;
+    let diverging = responses
+        .iter()
+        .enumerate()
+        .filter(|(_, response)| **response != responses[reference])
+        .map(|(id, _)| id.to_string())
+        .collect::<Vec<_>>();
+    if !diverging.is_empty() {
+        return Err(ApiErrors::NodesDiverged(format!(
+            "mpc node(s) {} diverged from node {reference} on {what}",
+            diverging.join(", ")
+        )));
+    }
+    Ok(responses.swap_remove(reference))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::agree;
+    use crate::error::ApiErrors;
+
+    fn diverged(responses: Vec<u64>) -> String {
+        match agree(responses, "the seed") {
+            Err(ApiErrors::NodesDiverged(message)) => message,
+            Err(err) => panic!("unexpected error {err}"),
+            Ok(response) => panic!("nodes agreed on {response}"),
+        }
+    }
+
+    #[test]
+    fn agree_on_same_responses() {
+        assert_eq!(agree(vec![7, 7, 7], "the seed").unwrap(), 7);
+    }
+
+    #[test]
+    fn report_node_deviating_from_majority() {
+        assert_eq!(
+            diverged(vec![7, 7, 8]),
+            "mpc node(s) 2 diverged from node 0 on the seed"
+        );
+        assert_eq!(
+            diverged(vec![8, 7, 7]),
+            "mpc node(s) 0 diverged from node 1 on the seed"
+        );
+    }
+
+    #[test]
+    fn report_all_but_first_node_without_majority() {
+        assert_eq!(
+            diverged(vec![6, 7, 8]),
+            "mpc node(s) 1, 2 diverged from node 0 on the seed"
+        );
+    }
+
+    #[test]
+    fn reject_no_responses() {
+        assert!(matches!(
+            agree(Vec::<u64>::new(), "the seed"),
+            Err(ApiErrors::InternalSeverError(_))
+        ));
+    }
+}
@@ -1,13 +1,82 @@
-use axum::{Router, routing::get};
+use axum::{
+    Router,
+    extract::FromRequestParts,
+    http::request::Parts,
+    routing::{get, post},
+};
+use uuid::Uuid;
 
-use crate::AppState;
+use crate::{AppState, error::ApiErrors};
 
+pub mod admin;
+pub mod jobs;
 pub mod user;
 
 pub fn create_routes(app_state: AppState) -> Router {
     Router::new()
-        .route("/sample_rand", get(user::sample_root_rand))
-        .route("/init_game", get(user::init_game))
-        .route("/reveal_door", get(user::reveal_door))
+        .route("/public_keys", get(user::public_keys))
+        .route("/vk_hashes", get(user::vk_hashes))
+        .route("/games", post(user::sample_root_rand))
+        .route("/games/{game_id}/init_game", post(user::init_game))
+        .route("/games/{game_id}/reveal_door", post(user::reveal_door))
+        .route("/games/{game_id}/finish_game", post(user::finish_game))
+        .route("/jobs/games", post(jobs::new_game))
+        .route("/jobs/games/{game_id}/init_game", post(jobs::init_game))
+        .route("/jobs/games/{game_id}/reveal_door", post(jobs::reveal_door))
+        .route("/jobs/games/{game_id}/finish_game", post(jobs::finish_game))
+        .route("/jobs/{job_id}", get(jobs::job))
+        .route("/jobs/{job_id}/events", get(jobs::job_events))
+        .route("/admin/refresh_shares", post(admin::refresh_shares))
+        .route("/admin/reconcile", post(admin::reconcile))
         .with_state(app_state)
 }
+
+/// The header a client sets to retry a request safely.
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+/// The namespace of the game ids derived from request ids.
+const GAME_ID_NAMESPACE: Uuid = Uuid::from_u128(0x6d6f_6e74_7968_616c_6c67_616d_655f_6964);
+
+/// The request id the orchestration sends to the mpc nodes with every
+/// request changing their state. It is the `Idempotency-Key` header of the
+/// client, or a fresh one if the client sets none. The nodes answer a
+/// request they saw before with their stored response, so a client
+/// retrying with the same key gets the original proof and commitments.
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The id of the game a new game request creates. A retried request
+    /// creates the same game.
+    pub fn game_id(&self) -> Uuid {
+        Uuid::new_v5(&GAME_ID_NAMESPACE, self.0.as_bytes())
+    }
+
+    /// The request id of one of several node requests of the same kind one
+    /// client request causes.
+    pub fn scoped(&self, scope: &str) -> String {
+        format!("{}.{scope}", self.0)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for RequestId {
+    type Rejection = ApiErrors;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let Some(key) = parts.headers.get(IDEMPOTENCY_KEY) else {
+            return Ok(Self(Uuid::new_v4().to_string()));
+        };
+        let key = key
+            .to_str()
+            .map_err(|_| ApiErrors::BadRequest("idempotency key is not ascii".to_owned()))?;
+        if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+            return Err(ApiErrors::BadRequest(format!(
+                "idempotency key must have 1 to {MAX_IDEMPOTENCY_KEY_LEN} characters"
+            )));
+        }
+        Ok(Self(key.to_owned()))
+    }
+}
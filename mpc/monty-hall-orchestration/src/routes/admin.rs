@@ -0,0 +1,263 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use axum::{Json, extract::State};
+use protos::monty_hall::{GamePhase, Job, JobStatus, RefreshSharesRequest, RollbackJobRequest};
+use serde::Serialize;
+
+use crate::{AppState, error::ApiResult, node_set::agree, routes::RequestId};
+
+/// How long a step completed on some nodes waits for the nodes without a job
+/// of it before it is rolled back. Their request may still be on the way,
+/// so this is more than the nodes wait for each other to open a session.
+const ABSENT_GRACE: Duration = Duration::from_secs(120);
+
+#[derive(Serialize)]
+pub struct RefreshSharesResponse {
+    pub refreshed_games: u64,
+}
+
+#[derive(Serialize)]
+pub struct ReconcileResponse {
+    /// The steps not all nodes completed with the same output, which the
+    /// nodes that completed them rolled back.
+    pub rolled_back: Vec<RolledBackStep>,
+    /// The number of steps still running on some node, or completed too
+    /// recently to tell whether the nodes without a job of them run them
+    /// yet, which are left as they are.
+    pub running: usize,
+}
+
+#[derive(Serialize)]
+pub struct RolledBackStep {
+    pub game_id: String,
+    pub phase: String,
+    /// The nodes that had completed the step.
+    pub nodes: Vec<usize>,
+}
+
+/// Re-randomizes the shares all nodes store. The nodes refresh together and
+/// hold back game steps meanwhile.
+pub async fn refresh_shares(
+    State(state): State<AppState>,
+    request_id: RequestId,
+) -> ApiResult<Json<RefreshSharesResponse>> {
+    tracing::info!("refreshing the shares of the mpc nodes");
+    let request = RefreshSharesRequest {
+        request_id: request_id.as_str().to_owned(),
+    };
+    let responses = state
+        .nodes
+        .broadcast_session(request, |node, request| async move {
+            node.refresh_shares(request).await
+        })
+        .await?;
+    let response = agree(responses, "refreshed games")?;
+    Ok(Json(RefreshSharesResponse {
+        refreshed_games: response.refreshed_games,
+    }))
+}
+
+/// Finds the game steps the nodes disagree on and rolls them back, so the
+/// games can continue from the last step all nodes agree on.
+///
+/// A step a node rolled back, e.g. because it crashed, or that the nodes
+/// completed with different outputs never reached the chain, as the nodes
+/// did not agree on it. So did a step a node has no job of, once the other
+/// nodes completed it [`ABSENT_GRACE`] ago. Steps still running on some node
+/// are left alone. The latest steps of a game are rolled back first.
+pub async fn reconcile(
+    State(state): State<AppState>,
+    request_id: RequestId,
+) -> ApiResult<Json<ReconcileResponse>> {
+    tracing::info!("reconciling the jobs of the mpc nodes");
+    let responses = state
+        .nodes
+        .broadcast((), |node, ()| async move { node.list_jobs().await })
+        .await?;
+    let num_nodes = responses.len();
+    let mut steps = BTreeMap::<(String, GamePhase), Vec<Option<Job>>>::new();
+    for (node, response) in responses.into_iter().enumerate() {
+        for job in response.jobs {
+            let key = (job.game_id.clone(), job.phase());
+            steps.entry(key).or_insert_with(|| vec![None; num_nodes])[node] = Some(job);
+        }
+    }
+
+    let mut response = ReconcileResponse {
+        rolled_back: Vec::new(),
+        running: 0,
+    };
+    for ((game_id, phase), jobs) in steps.into_iter().rev() {
+        match reconcile_step(&jobs) {
+            StepAction::Keep => continue,
+            StepAction::Wait => {
+                response.running += 1;
+                continue;
+            }
+            StepAction::RollBack => {}
+        }
+        let completed = jobs
+            .iter()
+            .map(|job| {
+                job.as_ref()
+                    .is_some_and(|job| job.status() == JobStatus::Completed)
+            })
+            .collect::<Vec<_>>();
+        let rollback_id = request_id.scoped(&format!("{game_id}.{}", phase.as_str_name()));
+        let requests = completed
+            .iter()
+            .map(|&completed| {
+                completed.then(|| RollbackJobRequest {
+                    game_id: game_id.clone(),
+                    phase: phase.into(),
+                    request_id: rollback_id.clone(),
+                })
+            })
+            .collect();
+        state
+            .nodes
+            .fan_out(requests, |node, request| async move {
+                match request {
+                    Some(request) => node.rollback_job(request).await.map(Some),
+                    None => Ok(None),
+                }
+            })
+            .await?;
+        tracing::warn!("rolled back {} of game {game_id}", phase.as_str_name());
+        response.rolled_back.push(RolledBackStep {
+            game_id,
+            phase: phase.as_str_name().to_lowercase(),
+            nodes: (0..num_nodes).filter(|&node| completed[node]).collect(),
+        });
+    }
+    Ok(Json(response))
+}
+
+/// What reconciling does with a step, see [`reconcile`].
+#[derive(Debug, PartialEq, Eq)]
+enum StepAction {
+    /// Nothing to undo, all nodes completed the step with the same output
+    /// or none completed it.
+    Keep,
+    /// The step may still complete on all nodes.
+    Wait,
+    /// The nodes that completed the step undo it.
+    RollBack,
+}
+
+/// Decides on a step from the jobs of the nodes, `None` for a node without
+/// a job of the step.
+fn reconcile_step(jobs: &[Option<Job>]) -> StepAction {
+    let present = jobs.iter().flatten().collect::<Vec<_>>();
+    let any = |status| present.iter().any(|job| job.status() == status);
+    if !any(JobStatus::Completed) {
+        return if any(JobStatus::Running) {
+            StepAction::Wait
+        } else {
+            StepAction::Keep
+        };
+    }
+    if any(JobStatus::RolledBack) {
+        return StepAction::RollBack;
+    }
+    if any(JobStatus::Running) {
+        return StepAction::Wait;
+    }
+    if present.iter().any(|job| job.output != present[0].output) {
+        return StepAction::RollBack;
+    }
+    if present.len() == jobs.len() {
+        return StepAction::Keep;
+    }
+    let completed_ago = present
+        .iter()
+        .map(|job| Duration::from_secs(job.age_secs))
+        .min()
+        .unwrap_or_default();
+    if completed_ago < ABSENT_GRACE {
+        StepAction::Wait
+    } else {
+        StepAction::RollBack
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use protos::monty_hall::{Job, JobStatus};
+
+    use super::{ABSENT_GRACE, StepAction, reconcile_step};
+
+    fn job(status: JobStatus, output: &[u8], age_secs: u64) -> Option<Job> {
+        let mut job = Job {
+            output: output.to_vec(),
+            age_secs,
+            ..Default::default()
+        };
+        job.set_status(status);
+        Some(job)
+    }
+
+    fn completed(output: &[u8]) -> Option<Job> {
+        job(JobStatus::Completed, output, 0)
+    }
+
+    #[test]
+    fn keep_step_all_nodes_agree_on() {
+        let jobs = [completed(b"seed"), completed(b"seed"), completed(b"seed")];
+        assert_eq!(reconcile_step(&jobs), StepAction::Keep);
+    }
+
+    #[test]
+    fn roll_back_diverged_step() {
+        let jobs = [completed(b"seed"), completed(b"seed"), completed(b"other")];
+        assert_eq!(reconcile_step(&jobs), StepAction::RollBack);
+    }
+
+    #[test]
+    fn wait_for_step_running_on_a_node() {
+        let jobs = [
+            completed(b"seed"),
+            job(JobStatus::Running, b"", 0),
+            completed(b"other"),
+        ];
+        assert_eq!(reconcile_step(&jobs), StepAction::Wait);
+    }
+
+    #[test]
+    fn roll_back_step_rolled_back_on_a_node() {
+        let jobs = [
+            completed(b"seed"),
+            job(JobStatus::RolledBack, b"", 0),
+            job(JobStatus::Running, b"", 0),
+        ];
+        assert_eq!(reconcile_step(&jobs), StepAction::RollBack);
+    }
+
+    #[test]
+    fn keep_step_no_node_completed() {
+        let jobs = [
+            job(JobStatus::RolledBack, b"", 0),
+            job(JobStatus::RolledBack, b"", 0),
+            None,
+        ];
+        assert_eq!(reconcile_step(&jobs), StepAction::Keep);
+    }
+
+    #[test]
+    fn wait_for_step_absent_on_a_node() {
+        let grace = ABSENT_GRACE.as_secs();
+        let jobs = [
+            job(JobStatus::Completed, b"seed", grace + 10),
+            None,
+            job(JobStatus::Completed, b"seed", grace - 1),
+        ];
+        assert_eq!(reconcile_step(&jobs), StepAction::Wait);
+        let jobs = [
+            job(JobStatus::Completed, b"seed", grace + 10),
+            None,
+            job(JobStatus::Completed, b"seed", grace),
+        ];
+        assert_eq!(reconcile_step(&jobs), StepAction::RollBack);
+    }
+}
@@ -0,0 +1,152 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::{Stream, StreamExt as _};
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    error::{ApiError, ApiErrors, ApiResult},
+    jobs::{JobEvent, JobInfo, JobKind},
+    routes::{
+        RequestId,
+        user::{self, FinishGameBody, RevealDoorBody},
+    },
+};
+
+/// The answer to a request started as job, poll `GET /jobs/{job_id}` or
+/// follow `GET /jobs/{job_id}/events` for its outcome.
+#[derive(Debug, Serialize)]
+pub struct JobCreatedResponse {
+    pub job_id: Uuid,
+}
+
+fn created(job_id: Uuid) -> (StatusCode, Json<JobCreatedResponse>) {
+    (StatusCode::ACCEPTED, Json(JobCreatedResponse { job_id }))
+}
+
+fn no_such_job(job_id: Uuid) -> ApiErrors {
+    ApiErrors::ExplicitError(ApiError::new(
+        StatusCode::NOT_FOUND,
+        format!("no job {job_id}"),
+    ))
+}
+
+/// Starts `POST /games` as job, the job result is the
+/// [`user::NewGameResponse`].
+pub async fn new_game(
+    State(state): State<AppState>,
+    request_id: RequestId,
+) -> (StatusCode, Json<JobCreatedResponse>) {
+    let jobs = state.jobs.clone();
+    created(
+        jobs.spawn(JobKind::NewGame, None, move |progress| async move {
+            user::run_new_game(&state, &request_id, &progress).await
+        }),
+    )
+}
+
+pub async fn init_game(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    request_id: RequestId,
+) -> (StatusCode, Json<JobCreatedResponse>) {
+    let jobs = state.jobs.clone();
+    created(jobs.spawn(
+        JobKind::InitGame,
+        Some(game_id),
+        move |progress| async move {
+            user::run_init_game(&state, game_id, &request_id, &progress).await
+        },
+    ))
+}
+
+/// Starts `POST /games/{game_id}/reveal_door` as job, the job result is the
+/// [`user::RevealDoorResponse`]. A malformed body is rejected right away.
+pub async fn reveal_door(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    request_id: RequestId,
+    Json(body): Json<RevealDoorBody>,
+) -> ApiResult<(StatusCode, Json<JobCreatedResponse>)> {
+    let requests = body.into_requests(game_id, &request_id, &state.nodes)?;
+    let jobs = state.jobs.clone();
+    Ok(created(
+        jobs.spawn(
+            JobKind::RevealDoor,
+            Some(game_id),
+            move |progress| async move {
+                user::run_reveal_door(&state, game_id, requests, &progress).await
+            },
+        ),
+    ))
+}
+
+pub async fn finish_game(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    request_id: RequestId,
+    Json(body): Json<FinishGameBody>,
+) -> ApiResult<(StatusCode, Json<JobCreatedResponse>)> {
+    let requests = body.into_requests(game_id, &request_id, &state.nodes)?;
+    let jobs = state.jobs.clone();
+    Ok(created(
+        jobs.spawn(
+            JobKind::FinishGame,
+            Some(game_id),
+            move |progress| async move {
+                user::run_finish_game(&state, game_id, requests, &progress).await
+            },
+        ),
+    ))
+}
+
+pub async fn job(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+) -> ApiResult<Json<JobInfo>> {
+    state
+        .jobs
+        .info(job_id)
+        .map(Json)
+        .ok_or_else(|| no_such_job(job_id))
+}
+
+/// Streams the events of a job as server-sent events, starting with the
+/// events so far. The stream ends with the `completed` or `failed` event.
+pub async fn job_events(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+) -> ApiResult<Sse<impl Stream<Item = Result<Event, axum::Error>>>> {
+    let (events, rx) = state
+        .jobs
+        .subscribe(job_id)
+        .ok_or_else(|| no_such_job(job_id))?;
+    let stream = futures::stream::iter(events)
+        .chain(futures::stream::unfold(rx, next_event))
+        .map(|event| Event::default().event(event.name()).json_data(&event));
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Receives the next event of a running job, until its final event.
+async fn next_event(
+    rx: Option<broadcast::Receiver<JobEvent>>,
+) -> Option<(JobEvent, Option<broadcast::Receiver<JobEvent>>)> {
+    let mut rx = rx?;
+    loop {
+        match rx.recv().await {
+            Ok(event @ (JobEvent::Completed { .. } | JobEvent::Failed { .. })) => {
+                return Some((event, None));
+            }
+            Ok(event) => return Some((event, Some(rx))),
+            // the full history stays available at `GET /jobs/{job_id}`
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
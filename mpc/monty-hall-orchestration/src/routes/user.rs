@@ -1,73 +1,452 @@
-use ark_serialize::CanonicalDeserialize;
+use std::str::FromStr;
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use axum::{
+    Json,
     extract::{Path, State},
     http::StatusCode,
 };
-use co_builder::prelude::ZeroKnowledge;
-use co_noir::{Bn254, Poseidon2Sponge, UltraHonk, VerifyingKey, VerifyingKeyBarretenberg};
+use protos::monty_hall::{
+    FinishGameRequest, GamePhase, GenerateNullifyingKeyRequest, InitGameRequest, RevealDoorRequest,
+    SampleRandRequest,
+};
+use serde::{Deserialize, Serialize};
 use ultrahonk::prelude::HonkProof;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    chain::{ChainStep, NullifyingPublicKey, ProofCapsules},
+    error::{ApiError, ApiErrors, ApiResult},
+    jobs::{JobProgress, Stage},
+    node_set::{NodeSet, agree},
+    routes::RequestId,
+    vks::CircuitVk,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NewGameResponse {
+    pub game_id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VkHashesResponse {
+    /// The decimal vk hashes of the init, choose and switch circuits, in the
+    /// order of `vk_hash` in the contract.
+    pub vk_hashes: [String; 3],
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublicKeysResponse {
+    /// The hex encoded crypto_box public keys of the mpc nodes, in the order
+    /// of the nodes.
+    pub public_keys: Vec<String>,
+}
+
+/// The committed door choice of the player. Every node gets its share of the
+/// door and the commitment randomness as hex encoded sealed box for its
+/// public key. The commitment is a decimal string. The nodes encrypt their
+/// shares of the opened door for the hex encoded `player_public_key`.
+#[derive(Debug, Deserialize)]
+pub struct RevealDoorBody {
+    pub door_ciphertexts: Vec<String>,
+    pub door_c: String,
+    pub player_public_key: String,
+}
+
+/// The opened door, committed to by `opened_door_c` (a decimal string). The
+/// player obtains the door and the commitment randomness by decrypting and
+/// combining the hex encoded shares of the nodes.
+#[derive(Debug, Serialize)]
+pub struct RevealDoorResponse {
+    pub opened_door_c: String,
+    pub opened_door_ciphertexts: Vec<String>,
+}
 
-use crate::{AppState, error::ApiResult};
-
-pub async fn sample_root_rand(State(state): State<AppState>) -> ApiResult<StatusCode> {
-    tracing::info!("creating new randomness!");
-    let (response0, response1, response2) = tokio::join!(
-        state.node0.sample_root_rand(),
-        state.node1.sample_root_rand(),
-        state.node2.sample_root_rand()
-    );
-    let response0 = response0?;
-    let response1 = response1?;
-    let response2 = response2?;
-
-    if response0.seed_c != response1.seed_c || response1.seed_c != response2.seed_c {
-        Err(eyre::eyre!("seed_commitment differ!"))?;
+impl RevealDoorBody {
+    /// Splits the body into the requests for the individual nodes.
+    pub(crate) fn into_requests(
+        self,
+        game_id: Uuid,
+        request_id: &RequestId,
+        nodes: &NodeSet,
+    ) -> ApiResult<Vec<RevealDoorRequest>> {
+        check_ciphertexts(&self.door_ciphertexts, nodes)?;
+        let door_c = parse_field(&self.door_c)?;
+        let player_public_key = parse_hex(&self.player_public_key)?;
+        self.door_ciphertexts
+            .iter()
+            .map(|ciphertext| {
+                Ok(RevealDoorRequest {
+                    game_id: game_id.to_string(),
+                    door_ciphertext: parse_hex(ciphertext)?,
+                    door_c: door_c.clone(),
+                    player_public_key: player_public_key.clone(),
+                    request_id: request_id.as_str().to_owned(),
+                })
+            })
+            .collect()
     }
-    let seed_commitment =
-        ark_bn254::Fr::deserialize_compressed(response0.seed_c.as_slice()).unwrap();
-    tracing::info!("got commitment to seed!");
-    tracing::info!("{seed_commitment}");
-    tracing::info!("sending seed commitment to chain (soon tm)");
-    // TODO SEND THIS ON CHAIN
-    Ok(StatusCode::OK)
 }
 
-pub async fn init_game(State(state): State<AppState>) -> ApiResult<StatusCode> {
-    let (response0, response1, response2) = tokio::join!(
-        state.node0.new_game(),
-        state.node1.new_game(),
-        state.node2.new_game()
-    );
-    let response0 = response0?;
-    let response1 = response1?;
-    let response2 = response2?;
-    if response0.proof != response1.proof || response1.proof != response2.proof {
-        Err(eyre::eyre!("proofs differ!"))?;
+/// The committed switch decision of the player. Every node gets its share
+/// of the decision and the commitment randomness as hex encoded sealed box
+/// for its public key. The commitment is a decimal string.
+#[derive(Debug, Deserialize)]
+pub struct FinishGameBody {
+    pub switch_ciphertexts: Vec<String>,
+    pub switch_c: String,
+}
+
+impl FinishGameBody {
+    /// Splits the body into the requests for the individual nodes.
+    pub(crate) fn into_requests(
+        self,
+        game_id: Uuid,
+        request_id: &RequestId,
+        nodes: &NodeSet,
+    ) -> ApiResult<Vec<FinishGameRequest>> {
+        check_ciphertexts(&self.switch_ciphertexts, nodes)?;
+        let switch_c = parse_field(&self.switch_c)?;
+        self.switch_ciphertexts
+            .iter()
+            .map(|ciphertext| {
+                Ok(FinishGameRequest {
+                    game_id: game_id.to_string(),
+                    switch_ciphertext: parse_hex(ciphertext)?,
+                    switch_c: switch_c.clone(),
+                    request_id: request_id.as_str().to_owned(),
+                })
+            })
+            .collect()
     }
-    if response0.game_state_c != response1.game_state_c
-        || response1.game_state_c != response2.game_state_c
-    {
-        Err(eyre::eyre!("seed_commitment differ!"))?;
+}
+
+fn check_ciphertexts(ciphertexts: &[String], nodes: &NodeSet) -> ApiResult<()> {
+    if ciphertexts.len() != nodes.len() {
+        return Err(ApiErrors::BadRequest(format!(
+            "expected {} ciphertexts, one per mpc node, but got {}",
+            nodes.len(),
+            ciphertexts.len()
+        )));
     }
+    Ok(())
+}
 
-    let init_vk = std::fs::read(state.init_vk_path).unwrap();
-    let init_vk = VerifyingKeyBarretenberg::<Bn254>::from_buffer(&init_vk).unwrap();
+fn parse_hex(value: &str) -> Result<Vec<u8>, ApiErrors> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value))
+        .map_err(|_| ApiErrors::BadRequest(format!("{value} is not a hex string")))
+}
 
-    let init_vk = VerifyingKey::from_barrettenberg_and_crs(init_vk, state.verifier_crs);
-    let proof = HonkProof::from_buffer(&response0.proof).expect("can deser proof");
+fn parse_field(value: &str) -> Result<Vec<u8>, ApiErrors> {
+    let field = ark_bn254::Fr::from_str(value)
+        .map_err(|_| ApiErrors::BadRequest(format!("{value} is not a field element")))?;
+    let mut bytes = Vec::new();
+    field
+        .serialize_compressed(&mut bytes)
+        .map_err(eyre::Report::from)?;
+    Ok(bytes)
+}
 
-    let result =
-        UltraHonk::<_, Poseidon2Sponge>::verify(proof, init_vk, ZeroKnowledge::Yes).unwrap();
+/// Packs a proof of the mpc nodes together with the vk of its circuit in the
+/// form the contract pops them from its capsules.
+fn proof_capsules(proof: &[u8], vk: &CircuitVk) -> eyre::Result<ProofCapsules> {
+    let proof = HonkProof::<ark_bn254::Fr>::from_buffer(proof)?;
+    Ok(ProofCapsules {
+        vk: vk.fields.clone(),
+        proof: proof.inner(),
+    })
+}
 
-    if result {
-        tracing::info!("retrieved proofs! Now sending them on chain (soon tm)");
-    } else {
-        tracing::error!("proofs do not verify! Rejected")
+/// Sends a step of a game to the chain, unless an earlier attempt of the
+/// request got it on chain already. A retried request then succeeds instead
+/// of failing on the contract, as the nodes answer it with the same
+/// commitment.
+async fn send_once(
+    state: &AppState,
+    game_id: Uuid,
+    step: ChainStep,
+    commitment: ark_bn254::Fr,
+    send: impl Future<Output = eyre::Result<()>>,
+) -> ApiResult<()> {
+    match state.chain.step_commitment(game_id, step).await? {
+        None => Ok(send.await?),
+        Some(sent) if sent == commitment => {
+            tracing::info!("{step:?} of game {game_id} is already on chain");
+            Ok(())
+        }
+        Some(_) => Err(ApiErrors::ExplicitError(ApiError::new(
+            StatusCode::CONFLICT,
+            format!("game {game_id} is on chain with another commitment for {step:?}"),
+        ))),
     }
-    // TODO SEND THIS ON CHAIN
+}
+
+pub async fn public_keys(State(state): State<AppState>) -> ApiResult<Json<PublicKeysResponse>> {
+    let responses = state
+        .nodes
+        .broadcast((), |node, ()| async move { node.get_public_key().await })
+        .await?;
+    Ok(Json(PublicKeysResponse {
+        public_keys: responses
+            .into_iter()
+            .map(|response| hex::encode(response.public_key))
+            .collect(),
+    }))
+}
+
+pub async fn vk_hashes(State(state): State<AppState>) -> Json<VkHashesResponse> {
+    Json(VkHashesResponse {
+        vk_hashes: state.vks.hashes().map(|hash| hash.to_string()),
+    })
+}
+
+/// Creates a new game. A request retried with the same idempotency key
+/// creates the same game and gets the original commitments of the nodes.
+pub async fn sample_root_rand(
+    State(state): State<AppState>,
+    request_id: RequestId,
+) -> ApiResult<Json<NewGameResponse>> {
+    run_new_game(&state, &request_id, &JobProgress::untracked())
+        .await
+        .map(Json)
+}
+
+pub async fn init_game(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    request_id: RequestId,
+) -> ApiResult<StatusCode> {
+    run_init_game(&state, game_id, &request_id, &JobProgress::untracked()).await?;
     Ok(StatusCode::OK)
 }
 
-pub async fn reveal_door(State(state): State<AppState>) -> ApiResult<StatusCode> {
-    todo!()
+pub async fn reveal_door(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    request_id: RequestId,
+    Json(body): Json<RevealDoorBody>,
+) -> ApiResult<Json<RevealDoorResponse>> {
+    let requests = body.into_requests(game_id, &request_id, &state.nodes)?;
+    run_reveal_door(&state, game_id, requests, &JobProgress::untracked())
+        .await
+        .map(Json)
+}
+
+pub async fn finish_game(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    request_id: RequestId,
+    Json(body): Json<FinishGameBody>,
+) -> ApiResult<StatusCode> {
+    let requests = body.into_requests(game_id, &request_id, &state.nodes)?;
+    run_finish_game(&state, game_id, requests, &JobProgress::untracked()).await?;
+    Ok(StatusCode::OK)
+}
+
+// The steps below are shared by the routes above and the jobs running them
+// in the background, see `routes::jobs`.
+
+pub(crate) async fn run_new_game(
+    state: &AppState,
+    request_id: &RequestId,
+    progress: &JobProgress,
+) -> ApiResult<NewGameResponse> {
+    let game_id = request_id.game_id();
+    tracing::info!("creating new randomness for game {game_id}!");
+    progress.stage(Stage::SampleRand);
+    let request = SampleRandRequest {
+        game_id: game_id.to_string(),
+        request_id: request_id.as_str().to_owned(),
+    };
+    let sample = state
+        .nodes
+        .broadcast_session(request, |node, request| async move {
+            node.sample_root_rand(request).await
+        });
+    let responses = progress
+        .watch_nodes(&state.nodes, game_id, GamePhase::Sampled, sample)
+        .await?;
+    let response = agree(responses, "seed commitment")?;
+    let seed_commitment = ark_bn254::Fr::deserialize_compressed(response.seed_c.as_slice())
+        .map_err(eyre::Report::from)?;
+    tracing::info!("got commitment to seed!");
+    tracing::info!("{seed_commitment}");
+
+    progress.stage(Stage::GenerateNullifyingKey);
+    let request = GenerateNullifyingKeyRequest {
+        game_id: game_id.to_string(),
+        request_id: request_id.as_str().to_owned(),
+    };
+    let generate = state
+        .nodes
+        .broadcast_session(request, |node, request| async move {
+            node.generate_nullifying_key(request).await
+        });
+    let responses = progress
+        .watch_nodes(&state.nodes, game_id, GamePhase::KeyGenerated, generate)
+        .await?;
+    let response = agree(responses, "nullifying public key")?;
+    let nullifying_pk = NullifyingPublicKey {
+        x: ark_bn254::Fr::deserialize_compressed(response.pk_x.as_slice())
+            .map_err(eyre::Report::from)?,
+        y: ark_bn254::Fr::deserialize_compressed(response.pk_y.as_slice())
+            .map_err(eyre::Report::from)?,
+        is_infinite: response.pk_is_infinite,
+    };
+    tracing::info!("got nullifying public key {nullifying_pk:?}");
+
+    progress.stage(Stage::Chain);
+    let vk_hashes = state.vks.hashes();
+    tracing::info!("sending seed commitment to chain");
+    send_once(
+        state,
+        game_id,
+        ChainStep::InitGame,
+        seed_commitment,
+        state
+            .chain
+            .init_game(game_id, seed_commitment, vk_hashes, nullifying_pk),
+    )
+    .await?;
+    Ok(NewGameResponse { game_id })
+}
+
+pub(crate) async fn run_init_game(
+    state: &AppState,
+    game_id: Uuid,
+    request_id: &RequestId,
+    progress: &JobProgress,
+) -> ApiResult<()> {
+    progress.stage(Stage::Prove);
+    let request = InitGameRequest {
+        game_id: game_id.to_string(),
+        request_id: request_id.as_str().to_owned(),
+    };
+    let init = state
+        .nodes
+        .broadcast_session(request, |node, request| async move {
+            node.new_game(request).await
+        });
+    let responses = progress
+        .watch_nodes(&state.nodes, game_id, GamePhase::Started, init)
+        .await?;
+    let response = agree(responses, "init proof and game state commitment")?;
+
+    progress.stage(Stage::Verify);
+    state.vks.init.verify(&response.proof)?;
+    tracing::info!("retrieved proofs! Now sending them on chain");
+    progress.stage(Stage::Chain);
+    let game_state_c = ark_bn254::Fr::deserialize_compressed(response.game_state_c.as_slice())
+        .map_err(eyre::Report::from)?;
+    let capsules = proof_capsules(&response.proof, &state.vks.init)?;
+    send_once(
+        state,
+        game_id,
+        ChainStep::StartGame,
+        game_state_c,
+        state.chain.start_game(game_id, game_state_c, capsules),
+    )
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn run_reveal_door(
+    state: &AppState,
+    game_id: Uuid,
+    requests: Vec<RevealDoorRequest>,
+    progress: &JobProgress,
+) -> ApiResult<RevealDoorResponse> {
+    progress.stage(Stage::Prove);
+    let reveal = state
+        .nodes
+        .fan_out_session(requests, |node, request| async move {
+            node.reveal_door(request).await
+        });
+    let mut responses = progress
+        .watch_nodes(&state.nodes, game_id, GamePhase::OpenedDoor, reveal)
+        .await?;
+    // the shares of the opened door are the only part that differs per node
+    let opened_door_ciphertexts = responses
+        .iter_mut()
+        .map(|response| hex::encode(std::mem::take(&mut response.opened_door_ciphertext)))
+        .collect();
+    let response = agree(responses, "reveal door response")?;
+    progress.stage(Stage::Verify);
+    state.vks.choose.verify(&response.proof)?;
+
+    let new_game_state_c =
+        ark_bn254::Fr::deserialize_compressed(response.new_game_state_c.as_slice())
+            .map_err(eyre::Report::from)?;
+    let opened_door_c = ark_bn254::Fr::deserialize_compressed(response.opened_door_c.as_slice())
+        .map_err(eyre::Report::from)?;
+    tracing::info!("opened a door!");
+    tracing::info!("new game state commitment: {new_game_state_c}");
+    tracing::info!("opened door commitment: {opened_door_c}");
+    let game_state_nullifier =
+        ark_bn254::Fr::deserialize_compressed(response.game_state_nullifier.as_slice())
+            .map_err(eyre::Report::from)?;
+    tracing::info!("sending proof to chain");
+    progress.stage(Stage::Chain);
+    let capsules = proof_capsules(&response.proof, &state.vks.choose)?;
+    send_once(
+        state,
+        game_id,
+        ChainStep::OpenDoor,
+        new_game_state_c,
+        state.chain.open_door(
+            game_id,
+            new_game_state_c,
+            opened_door_c,
+            game_state_nullifier,
+            capsules,
+        ),
+    )
+    .await?;
+    Ok(RevealDoorResponse {
+        opened_door_c: opened_door_c.to_string(),
+        opened_door_ciphertexts,
+    })
+}
+
+pub(crate) async fn run_finish_game(
+    state: &AppState,
+    game_id: Uuid,
+    requests: Vec<FinishGameRequest>,
+    progress: &JobProgress,
+) -> ApiResult<()> {
+    progress.stage(Stage::Prove);
+    let finish = state
+        .nodes
+        .fan_out_session(requests, |node, request| async move {
+            node.finish_game(request).await
+        });
+    let responses = progress
+        .watch_nodes(&state.nodes, game_id, GamePhase::Done, finish)
+        .await?;
+    let response = agree(responses, "finish game response")?;
+    progress.stage(Stage::Verify);
+    state.vks.switch.verify(&response.proof)?;
+
+    let win_c = ark_bn254::Fr::deserialize_compressed(response.win_c.as_slice())
+        .map_err(eyre::Report::from)?;
+    tracing::info!("finished the game!");
+    tracing::info!("win commitment: {win_c}");
+    let game_state_nullifier =
+        ark_bn254::Fr::deserialize_compressed(response.game_state_nullifier.as_slice())
+            .map_err(eyre::Report::from)?;
+    tracing::info!("sending proof to chain");
+    progress.stage(Stage::Chain);
+    let capsules = proof_capsules(&response.proof, &state.vks.switch)?;
+    send_once(
+        state,
+        game_id,
+        ChainStep::FinishGame,
+        win_c,
+        state
+            .chain
+            .finish_game(game_id, win_c, game_state_nullifier, capsules),
+    )
+    .await?;
+    Ok(())
 }
@@ -0,0 +1,170 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use ark_ff::PrimeField as _;
+use co_builder::prelude::{ProverCrs, ZeroKnowledge};
+use co_noir::{Bn254, CrsParser, Poseidon2Sponge, UltraHonk, Utils, VerifyingKey};
+use eyre::Context as _;
+use ultrahonk::prelude::HonkProof;
+
+use crate::{
+    chain::{self, VK_SIZE},
+    config::ServerConfig,
+    error::{ApiErrors, ApiResult},
+};
+
+/// The size of the prover crs, as the nodes read it.
+const CRS_SIZE: usize = 4096;
+
+/// The verification key of one circuit, derived once at startup.
+pub struct CircuitVk {
+    name: &'static str,
+    vk: VerifyingKey<Bn254>,
+    /// The vk as field elements, as the contract pops it from the capsules.
+    pub fields: Vec<ark_bn254::Fr>,
+    /// The hash of [`CircuitVk::fields`] the contract stores per game.
+    pub hash: ark_bn254::Fr,
+}
+
+/// The verification keys of the init, choose and switch circuits.
+pub struct CircuitVks {
+    pub init: CircuitVk,
+    pub choose: CircuitVk,
+    pub switch: CircuitVk,
+}
+
+impl CircuitVk {
+    /// Derives the vk from the compiled circuit the way the nodes do, so
+    /// both verify against the same key.
+    fn derive(
+        name: &'static str,
+        circuit_path: &Path,
+        crs: &Arc<ProverCrs<Bn254>>,
+        verifier_crs: ark_bn254::G2Affine,
+    ) -> eyre::Result<Self> {
+        let circuit = Utils::get_program_artifact_from_file(circuit_path).with_context(|| {
+            format!("while reading {name} circuit at {}", circuit_path.display())
+        })?;
+        let constraint_system = Utils::get_constraint_system_from_artifact(&circuit, true);
+        let vk =
+            co_noir::generate_vk_barretenberg::<Bn254>(&constraint_system, Arc::clone(crs), false)
+                .with_context(|| format!("while deriving {name} vk"))?;
+        // the contract verifies against the fields, we against the vk
+        let fields = vk_as_fields(&vk.to_buffer())
+            .with_context(|| format!("while deriving {name} vk fields"))?;
+        let vk = VerifyingKey::from_barrettenberg_and_crs(vk, verifier_crs);
+        let hash = chain::vk_hash(&fields);
+        tracing::info!("derived {name} vk with hash {hash}");
+        Ok(Self {
+            name,
+            vk,
+            fields,
+            hash,
+        })
+    }
+
+    /// Verifies a proof of the mpc nodes for this circuit.
+    pub fn verify(&self, proof: &[u8]) -> ApiResult<()> {
+        let name = self.name;
+        let proof = HonkProof::from_buffer(proof)
+            .map_err(|err| ApiErrors::InvalidProof(format!("cannot read {name} proof: {err}")))?;
+        // the nodes prove without zero knowledge
+        let verified =
+            UltraHonk::<_, Poseidon2Sponge>::verify(proof, self.vk.clone(), ZeroKnowledge::No)
+                .map_err(|err| {
+                    ApiErrors::InvalidProof(format!("cannot verify {name} proof: {err}"))
+                })?;
+        if !verified {
+            return Err(ApiErrors::InvalidProof(format!(
+                "{name} proof does not verify"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The fields of a vk as `bb vk_as_fields` writes them: the circuit size,
+/// the number of public inputs, their offset, the pairing point accumulator
+/// flag and indices, and every commitment as the 136 bit limbs of its
+/// coordinates. Reads the big-endian layout of `bb write_vk`, which
+/// additionally holds the log of the circuit size.
+fn vk_as_fields(vk: &[u8]) -> eyre::Result<Vec<ark_bn254::Fr>> {
+    const HEADER: usize = 4 * 8 + 1 + 16 * 4;
+    const POINT: usize = 64;
+    if vk.len() < HEADER || (vk.len() - HEADER) % POINT != 0 {
+        eyre::bail!("vk has an unexpected length of {} bytes", vk.len());
+    }
+    let (header, commitments) = vk.split_at(HEADER);
+    let u64_at =
+        |i: usize| u64::from_be_bytes(header[8 * i..8 * i + 8].try_into().expect("8 bytes"));
+    // skips the log of the circuit size
+    let mut fields = vec![u64_at(0), u64_at(2), u64_at(3), u64::from(header[32])]
+        .into_iter()
+        .chain(
+            header[33..]
+                .chunks_exact(4)
+                .map(|index| u64::from(u32::from_be_bytes(index.try_into().expect("4 bytes")))),
+        )
+        .map(ark_bn254::Fr::from)
+        .collect::<Vec<_>>();
+    for coordinate in commitments.chunks_exact(32) {
+        let (hi, lo) = coordinate.split_at(32 - 17);
+        fields.push(ark_bn254::Fr::from_be_bytes_mod_order(lo));
+        fields.push(ark_bn254::Fr::from_be_bytes_mod_order(hi));
+    }
+    if fields.len() != VK_SIZE {
+        eyre::bail!("vk has {} fields instead of {VK_SIZE}", fields.len());
+    }
+    Ok(fields)
+}
+
+impl CircuitVks {
+    pub fn load(config: &ServerConfig) -> eyre::Result<Self> {
+        let crs = Arc::new(
+            CrsParser::<Bn254>::get_crs_g1(&config.crs_path, CRS_SIZE, ZeroKnowledge::Yes)
+                .context("while reading crs")?,
+        );
+        let verifier_crs = CrsParser::<Bn254>::get_crs_g2(&config.verifier_crs)
+            .context("while reading verifier crs")?;
+        Ok(Self {
+            init: CircuitVk::derive("init", &config.init_circuit, &crs, verifier_crs)?,
+            choose: CircuitVk::derive("choose", &config.choose_circuit, &crs, verifier_crs)?,
+            switch: CircuitVk::derive("switch", &config.switch_circuit, &crs, verifier_crs)?,
+        })
+    }
+
+    /// The vk hashes in the order of `vk_hash: [Field; 3]` of the contract.
+    pub fn hashes(&self) -> [ark_bn254::Fr; 3] {
+        [self.init.hash, self.choose.hash, self.switch.hash]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn init_vk_as_fields() {
+        let vk = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/monty_hall_init.vk"
+        ))
+        .expect("init vk is checked in");
+        let fields = vk_as_fields(&vk).expect("init vk is valid");
+        // circuit size, public inputs, their offset and the accumulator flag
+        assert_eq!(fields[..4], [4096u64, 18, 1, 1].map(ark_bn254::Fr::from));
+        assert_eq!(fields[4], ark_bn254::Fr::from(2u64));
+        assert_eq!(fields[19], ark_bn254::Fr::from(17u64));
+    }
+
+    #[test]
+    fn reject_truncated_vk() {
+        let vk = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/monty_hall_init.vk"
+        ))
+        .expect("init vk is checked in");
+        assert!(vk_as_fields(&vk[..vk.len() - 1]).is_err());
+        assert!(vk_as_fields(&vk[..vk.len() - 64]).is_err());
+    }
+}
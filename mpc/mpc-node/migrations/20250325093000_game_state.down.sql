@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS nullifying_key;
+DROP TABLE IF EXISTS monty_hall_game_opened_door;
+DROP TABLE IF EXISTS monty_hall_game_state;
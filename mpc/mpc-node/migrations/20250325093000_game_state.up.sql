@@ -0,0 +1,22 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS monty_hall_game_state (
+                id SERIAL PRIMARY KEY,
+                game_state BYTEA NOT NULL,
+                game_state_r BYTEA NOT NULL,
+                game_state_c BYTEA NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS monty_hall_game_opened_door (
+                id SERIAL PRIMARY KEY,
+                proof BYTEA NOT NULL,
+                opened_door BYTEA NOT NULL,
+                opened_door_r BYTEA NOT NULL,
+                opened_door_c BYTEA NOT NULL,
+                game_state_nullifier BYTEA NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS nullifying_key (
+                id SERIAL PRIMARY KEY,
+                sk BYTEA NOT NULL,
+                pk BYTEA NOT NULL
+);
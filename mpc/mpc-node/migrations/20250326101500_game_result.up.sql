@@ -0,0 +1,9 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS monty_hall_game_result (
+                id SERIAL PRIMARY KEY,
+                proof BYTEA NOT NULL,
+                win BYTEA NOT NULL,
+                win_r BYTEA NOT NULL,
+                win_c BYTEA NOT NULL,
+                game_state_nullifier BYTEA NOT NULL
+);
@@ -0,0 +1,52 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS nullifying_key;
+DROP TABLE IF EXISTS monty_hall_game_result;
+DROP TABLE IF EXISTS monty_hall_game_opened_door;
+DROP TABLE IF EXISTS monty_hall_game_state;
+DROP TABLE IF EXISTS monty_hall_game_init_state;
+DROP TABLE IF EXISTS root_rand;
+
+CREATE TABLE IF NOT EXISTS root_rand (
+                id SERIAL PRIMARY KEY,
+                seed BYTEA NOT NULL,
+                seed_r BYTEA NOT NULL,
+                seed_c BYTEA NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS monty_hall_game_init_state (
+                id SERIAL PRIMARY KEY,
+                proof BYTEA NOT NULL,
+                game_state_r BYTEA NOT NULL,
+                game_state_c BYTEA NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS monty_hall_game_state (
+                id SERIAL PRIMARY KEY,
+                game_state BYTEA NOT NULL,
+                game_state_r BYTEA NOT NULL,
+                game_state_c BYTEA NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS monty_hall_game_opened_door (
+                id SERIAL PRIMARY KEY,
+                proof BYTEA NOT NULL,
+                opened_door BYTEA NOT NULL,
+                opened_door_r BYTEA NOT NULL,
+                opened_door_c BYTEA NOT NULL,
+                game_state_nullifier BYTEA NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS monty_hall_game_result (
+                id SERIAL PRIMARY KEY,
+                proof BYTEA NOT NULL,
+                win BYTEA NOT NULL,
+                win_r BYTEA NOT NULL,
+                win_c BYTEA NOT NULL,
+                game_state_nullifier BYTEA NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS nullifying_key (
+                id SERIAL PRIMARY KEY,
+                sk BYTEA NOT NULL,
+                pk BYTEA NOT NULL
+);
@@ -0,0 +1,54 @@
+-- Add up migration script here
+-- Every persisted state now belongs to a game. The old tables only ever held
+-- a single game, so we start over.
+DROP TABLE IF EXISTS root_rand;
+DROP TABLE IF EXISTS monty_hall_game_init_state;
+DROP TABLE IF EXISTS monty_hall_game_state;
+DROP TABLE IF EXISTS monty_hall_game_opened_door;
+DROP TABLE IF EXISTS monty_hall_game_result;
+DROP TABLE IF EXISTS nullifying_key;
+
+CREATE TABLE IF NOT EXISTS root_rand (
+                game_id UUID PRIMARY KEY,
+                seed BYTEA NOT NULL,
+                seed_r BYTEA NOT NULL,
+                seed_c BYTEA NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS monty_hall_game_init_state (
+                game_id UUID PRIMARY KEY REFERENCES root_rand(game_id),
+                proof BYTEA NOT NULL,
+                game_state_r BYTEA NOT NULL,
+                game_state_c BYTEA NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS monty_hall_game_state (
+                game_id UUID PRIMARY KEY REFERENCES root_rand(game_id),
+                game_state BYTEA NOT NULL,
+                game_state_r BYTEA NOT NULL,
+                game_state_c BYTEA NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS monty_hall_game_opened_door (
+                game_id UUID PRIMARY KEY REFERENCES root_rand(game_id),
+                proof BYTEA NOT NULL,
+                opened_door BYTEA NOT NULL,
+                opened_door_r BYTEA NOT NULL,
+                opened_door_c BYTEA NOT NULL,
+                game_state_nullifier BYTEA NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS monty_hall_game_result (
+                game_id UUID PRIMARY KEY REFERENCES root_rand(game_id),
+                proof BYTEA NOT NULL,
+                win BYTEA NOT NULL,
+                win_r BYTEA NOT NULL,
+                win_c BYTEA NOT NULL,
+                game_state_nullifier BYTEA NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS nullifying_key (
+                game_id UUID PRIMARY KEY REFERENCES root_rand(game_id),
+                sk BYTEA NOT NULL,
+                pk BYTEA NOT NULL
+);
@@ -0,0 +1,9 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS monty_hall_game_state;
+
+CREATE TABLE IF NOT EXISTS monty_hall_game_state (
+                game_id UUID PRIMARY KEY REFERENCES root_rand(game_id),
+                game_state BYTEA NOT NULL,
+                game_state_r BYTEA NOT NULL,
+                game_state_c BYTEA NOT NULL
+);
@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- Store every field of the shared game state in its own column instead of a
+-- serialized vector. Stored states of the old layout cannot be mapped, so we
+-- start over.
+DROP TABLE IF EXISTS monty_hall_game_state;
+
+CREATE TABLE IF NOT EXISTS monty_hall_game_state (
+                game_id UUID PRIMARY KEY REFERENCES root_rand(game_id),
+                state BYTEA NOT NULL,
+                prng_state BYTEA NOT NULL,
+                prng_position BYTEA NOT NULL,
+                winning_door BYTEA NOT NULL,
+                chosen_door BYTEA NOT NULL,
+                open_door BYTEA NOT NULL,
+                game_state_r BYTEA NOT NULL,
+                game_state_c BYTEA NOT NULL
+);
@@ -0,0 +1,7 @@
+-- Add down migration script here
+ALTER TABLE root_rand DROP COLUMN IF EXISTS protocol;
+ALTER TABLE nullifying_key DROP COLUMN IF EXISTS protocol;
+ALTER TABLE monty_hall_game_init_state DROP COLUMN IF EXISTS protocol;
+ALTER TABLE monty_hall_game_state DROP COLUMN IF EXISTS protocol;
+ALTER TABLE monty_hall_game_opened_door DROP COLUMN IF EXISTS protocol;
+ALTER TABLE monty_hall_game_result DROP COLUMN IF EXISTS protocol;
@@ -0,0 +1,9 @@
+-- Add up migration script here
+-- Tag every stored share with the MPC protocol it belongs to. All shares
+-- stored so far are rep3 shares.
+ALTER TABLE root_rand ADD COLUMN protocol TEXT NOT NULL DEFAULT 'rep3';
+ALTER TABLE nullifying_key ADD COLUMN protocol TEXT NOT NULL DEFAULT 'rep3';
+ALTER TABLE monty_hall_game_init_state ADD COLUMN protocol TEXT NOT NULL DEFAULT 'rep3';
+ALTER TABLE monty_hall_game_state ADD COLUMN protocol TEXT NOT NULL DEFAULT 'rep3';
+ALTER TABLE monty_hall_game_opened_door ADD COLUMN protocol TEXT NOT NULL DEFAULT 'rep3';
+ALTER TABLE monty_hall_game_result ADD COLUMN protocol TEXT NOT NULL DEFAULT 'rep3';
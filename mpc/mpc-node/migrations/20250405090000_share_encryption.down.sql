@@ -0,0 +1,8 @@
+-- Add down migration script here
+-- Shares encrypted before cannot be read anymore without their key version.
+ALTER TABLE root_rand DROP COLUMN IF EXISTS key_version;
+ALTER TABLE nullifying_key DROP COLUMN IF EXISTS key_version;
+ALTER TABLE monty_hall_game_init_state DROP COLUMN IF EXISTS key_version;
+ALTER TABLE monty_hall_game_state DROP COLUMN IF EXISTS key_version;
+ALTER TABLE monty_hall_game_opened_door DROP COLUMN IF EXISTS key_version;
+ALTER TABLE monty_hall_game_result DROP COLUMN IF EXISTS key_version;
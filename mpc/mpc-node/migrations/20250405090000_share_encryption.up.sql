@@ -0,0 +1,11 @@
+-- Add up migration script here
+-- Shares are encrypted at rest with a key derived from the key phrase of the
+-- node. The key version of a row tells which key encrypted its shares, 0
+-- marks the plaintext shares stored so far. The node encrypts them with the
+-- current key on startup.
+ALTER TABLE root_rand ADD COLUMN key_version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE nullifying_key ADD COLUMN key_version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE monty_hall_game_init_state ADD COLUMN key_version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE monty_hall_game_state ADD COLUMN key_version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE monty_hall_game_opened_door ADD COLUMN key_version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE monty_hall_game_result ADD COLUMN key_version INTEGER NOT NULL DEFAULT 0;
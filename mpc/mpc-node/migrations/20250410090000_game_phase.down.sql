@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE root_rand DROP COLUMN IF EXISTS phase;
@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- Track the phase of every game, so the node only runs the steps of a game
+-- in order. The phase of the games stored so far follows from their rows.
+ALTER TABLE root_rand ADD COLUMN phase TEXT NOT NULL DEFAULT 'sampled';
+
+UPDATE root_rand SET phase = CASE
+    WHEN EXISTS (SELECT 1 FROM monty_hall_game_result r WHERE r.game_id = root_rand.game_id) THEN 'done'
+    WHEN EXISTS (SELECT 1 FROM monty_hall_game_opened_door o WHERE o.game_id = root_rand.game_id) THEN 'opened_door'
+    WHEN EXISTS (SELECT 1 FROM monty_hall_game_init_state i WHERE i.game_id = root_rand.game_id) THEN 'started'
+    WHEN EXISTS (SELECT 1 FROM nullifying_key n WHERE n.game_id = root_rand.game_id) THEN 'key_generated'
+    ELSE 'sampled'
+END;
@@ -0,0 +1,14 @@
+-- Add up migration script here
+-- The write-ahead log of the game steps. A job is recorded as running
+-- before its MPC starts and completed in the transaction storing its
+-- result. The input holds the encrypted game state a step replaces, so the
+-- step can be rolled back. Refreshing the shares clears the log.
+CREATE TABLE IF NOT EXISTS mpc_job (
+                game_id UUID NOT NULL,
+                phase TEXT NOT NULL,
+                status TEXT NOT NULL,
+                key_version INTEGER NOT NULL,
+                input BYTEA,
+                output BYTEA,
+                PRIMARY KEY (game_id, phase)
+);
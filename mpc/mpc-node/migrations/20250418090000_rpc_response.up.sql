@@ -0,0 +1,14 @@
+-- Add up migration script here
+-- The responses of the requests changing the state of the node, keyed by
+-- the request id of the caller. A repeated request is answered from here.
+-- Responses of game steps carry the game and phase, so rolling back the
+-- step drops them.
+CREATE TABLE IF NOT EXISTS rpc_response (
+                method TEXT NOT NULL,
+                request_id TEXT NOT NULL,
+                request_hash BYTEA NOT NULL,
+                game_id UUID,
+                phase TEXT,
+                response BYTEA NOT NULL,
+                PRIMARY KEY (method, request_id)
+);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DELETE FROM rpc_response WHERE response IS NULL;
+ALTER TABLE rpc_response ALTER COLUMN response SET NOT NULL;
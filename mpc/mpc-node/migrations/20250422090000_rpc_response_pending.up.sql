@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- A request reserves its request id with a pending row without response
+-- before it runs, a repeated request waits for the response instead of
+-- running the request a second time.
+ALTER TABLE rpc_response ALTER COLUMN response DROP NOT NULL;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE mpc_job DROP COLUMN updated_at;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- When a job last changed its status. Reconciling the nodes waits before it
+-- rolls back a step a node has no job of, as its request may still be on
+-- the way.
+ALTER TABLE mpc_job ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
@@ -0,0 +1,22 @@
+#!/usr/bin/env bash
+# Compiles the circuits the nodes prove and copies the artifacts to
+# data/circuits. Run from mpc-node/scripts, commit the artifacts.
+set -euo pipefail
+
+NARGO_VERSION=1.0.0-beta.3 ##the nargo version matching the acir version of co-noir
+
+CIRCUITS=(monty_hall_choose monty_hall_switch nullifying_key)
+
+## install noirup: curl -L https://raw.githubusercontent.com/noir-lang/noirup/main/install | bash
+r=$(bash -c "nargo --version")
+if  [[ $r != "nargo version = $NARGO_VERSION"* ]];
+then
+    bash -c "noirup -v ${NARGO_VERSION}"
+fi
+
+NOIR_LOGIC="$(cd ../../../noir_logic && pwd)"
+CIRCUITS_DIR="$(cd ../data/circuits && pwd)"
+for circuit in "${CIRCUITS[@]}"; do
+    (cd "${NOIR_LOGIC}/${circuit}" && nargo compile)
+    cp "${NOIR_LOGIC}/${circuit}/target/${circuit}.json" "${CIRCUITS_DIR}/${circuit}.json"
+done
@@ -1,4 +1,7 @@
-use std::{net::SocketAddr, path::PathBuf};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
 
 use clap::Parser;
 use co_noir::NetworkConfig;
@@ -15,14 +18,30 @@ pub struct NodeConfig {
     #[clap(long, env = "NODE_CRS_PATH")]
     pub crs_path: PathBuf,
 
-    /// The path to the init circuit
+    /// The path to the verifier CRS
+    #[clap(long, env = "NODE_VERIFIER_CRS_PATH")]
+    pub verifier_crs_path: PathBuf,
+
+    /// The path to the commit circuit
     #[clap(long, env = "NODE_COMMIT_CIRCUIT")]
     pub commit_circuit: PathBuf,
 
+    /// The path to the nullifying key circuit
+    #[clap(long, env = "NODE_NULLIFYING_KEY_CIRCUIT")]
+    pub nullifying_key_circuit: PathBuf,
+
     /// The path to the init circuit
     #[clap(long, env = "NODE_INIT_CIRCUIT")]
     pub init_circuit: PathBuf,
 
+    /// The path to the choose circuit
+    #[clap(long, env = "NODE_CHOOSE_CIRCUIT")]
+    pub choose_circuit: PathBuf,
+
+    /// The path to the switch circuit
+    #[clap(long, env = "NODE_SWITCH_CIRCUIT")]
+    pub switch_circuit: PathBuf,
+
     /// The path to the network config file
     #[clap(long, env = "NODE_NETWORK_CONFIG")]
     pub network_config: PathBuf,
@@ -31,6 +50,36 @@ pub struct NodeConfig {
     #[clap(long, env = "NODE_KEY_PHRASE")]
     pub key_phrase: SecretString,
 
+    /// Instead of serving, hand the shares of the party with this id over to
+    /// the party replacing it in the new network config, then exit. The
+    /// other two parties and the replacement run it at the same time
+    #[clap(long, env = "NODE_REPLACE_PARTY", requires = "new_network_config")]
+    pub replace_party: Option<usize>,
+
+    /// The network config with the replacement in place of the replaced
+    /// party. The hand-off runs on it and writes it to the network config
+    /// path once the shares are handed off
+    #[clap(long, env = "NODE_NEW_NETWORK_CONFIG", requires = "replace_party")]
+    pub new_network_config: Option<PathBuf>,
+
+    /// The key material of the keys encrypting the shares at rest, as
+    /// comma separated `version=secret` pairs. Every version needs its own
+    /// secret, keep the old versions until the shares are re-encrypted
+    #[clap(long, env = "NODE_STORAGE_KEYS")]
+    pub storage_keys: SecretString,
+
+    /// The version of the key the shares are encrypted with at rest. Add a
+    /// secret for a new version and bump it to rotate the key, the node
+    /// re-encrypts all shares on startup
+    #[clap(long, env = "NODE_STORAGE_KEY_VERSION", default_value_t = 1, value_parser = clap::value_parser!(i32).range(1..))]
+    pub storage_key_version: i32,
+
+    /// Encrypt the shares stored in plaintext before shares were encrypted
+    /// at rest, once. Without it the node refuses to start while plaintext
+    /// shares are stored
+    #[clap(long, env = "NODE_MIGRATE_PLAINTEXT_SHARES")]
+    pub migrate_plaintext_shares: bool,
+
     /// The url to the postgres db
     #[clap(long, env = "NODE_PERSISTENT_STORAGE")]
     pub postgres_url: String,
@@ -38,12 +87,33 @@ pub struct NodeConfig {
 
 impl NodeConfig {
     pub(crate) fn network_config(&self) -> eyre::Result<NetworkConfig> {
-        let toml = std::fs::read_to_string(&self.network_config)?;
-        let config_file = toml::from_str::<NetworkConfigFile>(&toml)?;
-        tracing::info!(
-            "reading from config file: {}",
-            self.network_config.display()
-        );
-        Ok(NetworkConfig::try_from(config_file)?)
+        read_network_config(&self.network_config)
+    }
+
+    /// The network config the node runs with, the new one during a
+    /// hand-off.
+    fn running_network_config(&self) -> eyre::Result<NetworkConfig> {
+        match &self.new_network_config {
+            Some(path) => read_network_config(path),
+            None => self.network_config(),
+        }
     }
+
+    /// Checks that the network config has as many parties as rep3 needs.
+    /// co-noir only extends witnesses on rep3 shares and every step of the
+    /// game extends a witness, so the nodes run rep3 only.
+    pub(crate) fn check_parties(&self) -> eyre::Result<()> {
+        let num_parties = self.running_network_config()?.parties.len();
+        if num_parties != 3 {
+            eyre::bail!("rep3 needs 3 parties but {num_parties} are configured");
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn read_network_config(path: &Path) -> eyre::Result<NetworkConfig> {
+    let toml = std::fs::read_to_string(path)?;
+    let config_file = toml::from_str::<NetworkConfigFile>(&toml)?;
+    tracing::info!("reading from config file: {}", path.display());
+    Ok(NetworkConfig::try_from(config_file)?)
 }
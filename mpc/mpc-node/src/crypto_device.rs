@@ -1,6 +1,21 @@
+use std::collections::{HashMap, HashSet};
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use chacha20poly1305::aead::{Aead as _, AeadCore as _, KeyInit as _, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use crypto_box::aead::OsRng;
 use secrecy::ExposeSecret;
 
 use crate::config::NodeConfig;
+use crate::mpc::ArithmeticShare;
+
+/// The share of a committed player input one node receives: the share of the
+/// value and the shares of the commitment randomness.
+pub(crate) type PlayerInputShare = (ArithmeticShare, [ArithmeticShare; 2]);
+
+/// The share of a committed output one node sends to the player: the share
+/// of the value and the share of the commitment randomness.
+pub(crate) type PlayerOutputShare = (ArithmeticShare, ArithmeticShare);
 
 pub struct CryptoDevice {
     sk: crypto_box::SecretKey,
@@ -20,6 +35,152 @@ impl CryptoDevice {
             sk: derive_secret_keys_from_seed(config.key_phrase.expose_secret()),
         }
     }
+
+    /// The key players encrypt their inputs for this node with.
+    pub(crate) fn public_key(&self) -> crypto_box::PublicKey {
+        self.sk.public_key()
+    }
+
+    /// Opens a sealed box the player encrypted for this node and
+    /// deserializes our share of the player input.
+    pub(crate) fn decrypt_player_input(&self, ciphertext: &[u8]) -> eyre::Result<PlayerInputShare> {
+        let plaintext = self
+            .sk
+            .unseal(ciphertext)
+            .map_err(|_| eyre::eyre!("cannot decrypt player input"))?;
+        Ok(PlayerInputShare::deserialize_compressed(
+            plaintext.as_slice(),
+        )?)
+    }
+
+    /// Seals our share of an output for the player.
+    pub(crate) fn encrypt_player_output(
+        &self,
+        player_public_key: &crypto_box::PublicKey,
+        share: &PlayerOutputShare,
+    ) -> eyre::Result<Vec<u8>> {
+        let mut plaintext = Vec::new();
+        share.serialize_compressed(&mut plaintext)?;
+        player_public_key
+            .seal(&mut OsRng, &plaintext)
+            .map_err(|_| eyre::eyre!("cannot encrypt player output"))
+    }
+}
+
+/// Encrypts the shares a node stores at rest. Every key version derives
+/// its key from its own secret of the config, so the key is rotated by
+/// adding a secret for a new version and bumping the version.
+pub(crate) struct StorageCipher {
+    version: i32,
+    ciphers: HashMap<i32, XChaCha20Poly1305>,
+}
+
+impl StorageCipher {
+    /// The key version of rows stored before shares were encrypted.
+    pub(crate) const PLAINTEXT: i32 = 0;
+
+    pub(crate) fn init(config: &NodeConfig) -> eyre::Result<Self> {
+        Self::new(
+            config.storage_key_version,
+            config.storage_keys.expose_secret(),
+        )
+    }
+
+    /// Derives the keys of the comma separated `version=secret` pairs of
+    /// `storage_keys`, new ciphertexts are encrypted with the key of
+    /// `version`.
+    fn new(version: i32, storage_keys: &str) -> eyre::Result<Self> {
+        let mut secrets = HashSet::new();
+        let mut ciphers = HashMap::new();
+        for entry in storage_keys.split(',') {
+            let (key_version, secret) = entry
+                .split_once('=')
+                .ok_or_else(|| eyre::eyre!("storage keys are no version=secret pairs"))?;
+            let key_version = key_version
+                .trim()
+                .parse::<i32>()
+                .ok()
+                .filter(|key_version| *key_version > Self::PLAINTEXT)
+                .ok_or_else(|| eyre::eyre!("invalid storage key version {key_version}"))?;
+            if secret.is_empty() {
+                eyre::bail!("empty secret for storage key version {key_version}");
+            }
+            if !secrets.insert(secret) {
+                eyre::bail!(
+                    "storage key version {key_version} reuses the secret of another version"
+                );
+            }
+            let key = derive_storage_key_from_seed(secret, key_version);
+            if ciphers
+                .insert(key_version, XChaCha20Poly1305::new(&key.into()))
+                .is_some()
+            {
+                eyre::bail!("storage key version {key_version} is configured twice");
+            }
+        }
+        if !ciphers.contains_key(&version) {
+            eyre::bail!("no secret for the current storage key version {version}");
+        }
+        Ok(Self { version, ciphers })
+    }
+
+    /// The key version new ciphertexts are encrypted with.
+    pub(crate) fn version(&self) -> i32 {
+        self.version
+    }
+
+    /// Encrypts with the current key. The nonce is prepended to the
+    /// ciphertext, `aad` binds it to where it is stored.
+    pub(crate) fn encrypt(&self, plaintext: &[u8], aad: &[u8]) -> eyre::Result<Vec<u8>> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self.ciphers[&self.version]
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad,
+                },
+            )
+            .map_err(|_| eyre::eyre!("cannot encrypt share"))?;
+        Ok([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    /// Decrypts with the key of `version`. Plaintext is not authenticated,
+    /// so shares stored in plaintext are only read by the migration
+    /// encrypting them.
+    pub(crate) fn decrypt(&self, version: i32, stored: &[u8], aad: &[u8]) -> eyre::Result<Vec<u8>> {
+        if version == Self::PLAINTEXT {
+            eyre::bail!(
+                "share is stored in plaintext, run the node once with --migrate-plaintext-shares"
+            );
+        }
+        let nonce_size = XNonce::default().len();
+        if stored.len() < nonce_size {
+            eyre::bail!("stored share is too short");
+        }
+        let (nonce, ciphertext) = stored.split_at(nonce_size);
+        let payload = Payload {
+            msg: ciphertext,
+            aad,
+        };
+        let cipher = self
+            .ciphers
+            .get(&version)
+            .ok_or_else(|| eyre::eyre!("no secret for storage key version {version}"))?;
+        cipher
+            .decrypt(XNonce::from_slice(nonce), payload)
+            .map_err(|_| eyre::eyre!("cannot decrypt share with key version {version}"))
+    }
+}
+
+pub fn derive_storage_key_from_seed(seed: &str, version: i32) -> [u8; 32] {
+    let salt = b"csn_kdf_salt_v1";
+    let storage_key_info = format!("csn_storage_encryption_key_v{version}");
+    let kdf = hkdf::Hkdf::<sha2::Sha256>::new(Some(&salt[..]), seed.as_bytes());
+    let mut storage_key = [0u8; 32];
+    kdf.expand(storage_key_info.as_bytes(), &mut storage_key)
+        .expect("Failed to expand storage key");
+    storage_key
 }
 
 pub fn derive_secret_keys_from_seed(seed: &str) -> crypto_box::SecretKey {
@@ -31,3 +192,60 @@ pub fn derive_secret_keys_from_seed(seed: &str) -> crypto_box::SecretKey {
         .expect("Failed to expand encryption key");
     crypto_box::SecretKey::from_bytes(secret_key1)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::StorageCipher;
+
+    const AAD: &[u8] = b"root_rand.seed.00000000-0000-0000-0000-000000000000";
+
+    #[test]
+    fn seal_open_round_trip() {
+        let cipher = StorageCipher::new(1, "1=first secret").unwrap();
+        let sealed = cipher.encrypt(b"share", AAD).unwrap();
+        assert_ne!(&sealed[..], b"share");
+        assert_eq!(cipher.decrypt(1, &sealed, AAD).unwrap(), b"share");
+    }
+
+    #[test]
+    fn open_rotated_key() {
+        let old = StorageCipher::new(1, "1=first secret").unwrap();
+        let sealed = old.encrypt(b"share", AAD).unwrap();
+        let rotated = StorageCipher::new(2, "1=first secret,2=second secret").unwrap();
+        assert_eq!(rotated.version(), 2);
+        assert_eq!(rotated.decrypt(1, &sealed, AAD).unwrap(), b"share");
+        let resealed = rotated.encrypt(b"share", AAD).unwrap();
+        assert_eq!(rotated.decrypt(2, &resealed, AAD).unwrap(), b"share");
+    }
+
+    #[test]
+    fn reject_wrong_aad() {
+        let cipher = StorageCipher::new(1, "1=first secret").unwrap();
+        let sealed = cipher.encrypt(b"share", AAD).unwrap();
+        let other = b"root_rand.seed_r.00000000-0000-0000-0000-000000000000";
+        assert!(cipher.decrypt(1, &sealed, other).is_err());
+    }
+
+    #[test]
+    fn reject_wrong_version() {
+        let cipher = StorageCipher::new(2, "1=first secret,2=second secret").unwrap();
+        let sealed = cipher.encrypt(b"share", AAD).unwrap();
+        assert!(cipher.decrypt(1, &sealed, AAD).is_err());
+        assert!(cipher.decrypt(3, &sealed, AAD).is_err());
+        assert!(
+            cipher
+                .decrypt(StorageCipher::PLAINTEXT, &sealed, AAD)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn reject_invalid_storage_keys() {
+        assert!(StorageCipher::new(1, "first secret").is_err());
+        assert!(StorageCipher::new(1, "0=first secret").is_err());
+        assert!(StorageCipher::new(1, "1=").is_err());
+        assert!(StorageCipher::new(2, "1=first secret").is_err());
+        assert!(StorageCipher::new(2, "1=same secret,2=same secret").is_err());
+        assert!(StorageCipher::new(1, "1=first secret,1=second secret").is_err());
+    }
+}
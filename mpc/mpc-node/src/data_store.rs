@@ -1,19 +1,214 @@
+use std::time::Duration;
+
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use sqlx::{PgPool, migrate::Migrator, postgres::PgPoolOptions, prelude::FromRow};
+use eyre::Context as _;
+use sqlx::{PgPool, Row as _, migrate::Migrator, postgres::PgPoolOptions, prelude::FromRow};
+use uuid::Uuid;
 
 use crate::{
     config::NodeConfig,
-    mpc::{ArithmeticShare, InitState, RootRandomness},
+    crypto_device::StorageCipher,
+    game_state::SharedGameState,
+    idempotency::{Reservation, StoredResponse},
+    jobs::{CannotRollBack, DuplicateJob, Job, JobStatus, JobsRunning},
+    mpc::{
+        ArithmeticShare, FinishGameState, GameState, InitState, NullifyingKey, RevealDoorState,
+        RootRandomness,
+    },
+    phase::{GamePhase, InvalidTransition},
 };
 
 static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
+/// The columns of a table keyed by the game id, split into the columns
+/// holding public values and the columns holding rep3 shares.
+struct Columns {
+    name: &'static str,
+    public: &'static [&'static str],
+    shares: &'static [&'static str],
+}
+
+impl Columns {
+    fn all(&self) -> Vec<&'static str> {
+        self.public.iter().chain(self.shares).copied().collect()
+    }
+}
+
+/// All tables keyed by the game id, every table after the ones it
+/// references.
+const TABLES: [Columns; 6] = [
+    Columns {
+        name: "root_rand",
+        public: &["seed_c"],
+        shares: &["seed", "seed_r"],
+    },
+    Columns {
+        name: "nullifying_key",
+        public: &["pk"],
+        shares: &["sk"],
+    },
+    Columns {
+        name: "monty_hall_game_init_state",
+        public: &["proof", "game_state_c"],
+        shares: &["game_state_r"],
+    },
+    Columns {
+        name: "monty_hall_game_state",
+        public: &["game_state_c"],
+        shares: &[
+            "state",
+            "prng_state",
+            "prng_position",
+            "winning_door",
+            "chosen_door",
+            "open_door",
+            "game_state_r",
+        ],
+    },
+    Columns {
+        name: "monty_hall_game_opened_door",
+        public: &["proof", "opened_door_c", "game_state_nullifier"],
+        shares: &["opened_door", "opened_door_r"],
+    },
+    Columns {
+        name: "monty_hall_game_result",
+        public: &["proof", "win_c", "game_state_nullifier"],
+        shares: &["win", "win_r"],
+    },
+];
+
+/// Derives the phase of every game from the rows stored for it.
+const PHASE_FROM_ROWS: &str = "UPDATE root_rand SET phase = CASE
+    WHEN EXISTS (SELECT 1 FROM monty_hall_game_result r WHERE r.game_id = root_rand.game_id) THEN 'done'
+    WHEN EXISTS (SELECT 1 FROM monty_hall_game_opened_door o WHERE o.game_id = root_rand.game_id) THEN 'opened_door'
+    WHEN EXISTS (SELECT 1 FROM monty_hall_game_init_state i WHERE i.game_id = root_rand.game_id) THEN 'started'
+    WHEN EXISTS (SELECT 1 FROM nullifying_key n WHERE n.game_id = root_rand.game_id) THEN 'key_generated'
+    ELSE 'sampled'
+END";
+
+/// A row of a table keyed by the game id. A share column holds one or more
+/// shares (e.g. the prng state holds four).
+pub(crate) struct StoredRow {
+    pub(crate) table: usize,
+    pub(crate) game_id: Uuid,
+    pub(crate) public: Vec<Vec<u8>>,
+    pub(crate) shares: Vec<Vec<ArithmeticShare>>,
+}
+
+/// A stored response of a request, see [`DbStore::reserve_response`].
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ResponseRow {
+    pub(crate) method: String,
+    pub(crate) request_id: String,
+    pub(crate) request_hash: Vec<u8>,
+    pub(crate) game_id: Option<Uuid>,
+    pub(crate) phase: Option<String>,
+    pub(crate) response: Vec<u8>,
+}
+
+impl StoredRow {
+    /// The number of public and share columns of `table`.
+    pub(crate) fn num_columns(table: usize) -> eyre::Result<(usize, usize)> {
+        let columns = TABLES
+            .get(table)
+            .ok_or_else(|| eyre::eyre!("unknown table {table}"))?;
+        Ok((columns.public.len(), columns.shares.len()))
+    }
+
+    /// Which shares the row holds: the table, the game id and the number
+    /// of shares of every share column.
+    pub(crate) fn layout(&self) -> Vec<ark_bn254::Fr> {
+        let mut layout = vec![
+            ark_bn254::Fr::from(self.table as u64),
+            ark_bn254::Fr::from(self.game_id.as_u128()),
+        ];
+        layout.extend(
+            self.shares
+                .iter()
+                .map(|shares| ark_bn254::Fr::from(shares.len() as u64)),
+        );
+        layout
+    }
+
+    pub(crate) fn shares_mut(&mut self) -> impl Iterator<Item = &mut ArithmeticShare> {
+        self.shares.iter_mut().flatten()
+    }
+}
+
+fn deserialize_shares(mut bytes: &[u8]) -> eyre::Result<Vec<ArithmeticShare>> {
+    let mut shares = Vec::new();
+    while !bytes.is_empty() {
+        shares.push(ArithmeticShare::deserialize_uncompressed(&mut bytes)?);
+    }
+    Ok(shares)
+}
+
+fn serialize_shares(shares: &[ArithmeticShare]) -> eyre::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for share in shares {
+        share.serialize_uncompressed(&mut bytes)?;
+    }
+    Ok(bytes)
+}
+
+async fn fetch_phase(
+    executor: impl sqlx::PgExecutor<'_>,
+    game_id: Uuid,
+) -> eyre::Result<Option<GamePhase>> {
+    sqlx::query_scalar::<_, String>("SELECT phase FROM root_rand WHERE game_id = $1")
+        .bind(game_id)
+        .fetch_optional(executor)
+        .await?
+        .map(|phase| GamePhase::from_tag(&phase))
+        .transpose()
+}
+
+/// Serializes a game state as the input of a job.
+fn serialize_game_state(game_state: &GameState) -> eyre::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    game_state.game_state_c.serialize_uncompressed(&mut bytes)?;
+    let mut shares = game_state.game_state.as_field_array().to_vec();
+    shares.push(game_state.game_state_r.clone());
+    bytes.extend(serialize_shares(&shares)?);
+    Ok(bytes)
+}
+
+fn deserialize_game_state(mut bytes: &[u8]) -> eyre::Result<GameState> {
+    let game_state_c = ark_bn254::Fr::deserialize_uncompressed(&mut bytes)?;
+    let mut shares = deserialize_shares(bytes)?;
+    let game_state_r = shares
+        .pop()
+        .ok_or_else(|| eyre::eyre!("game state input has no shares"))?;
+    Ok(GameState {
+        game_state: SharedGameState::try_from(shares)?,
+        game_state_r,
+        game_state_c,
+    })
+}
+
+/// The protocol tag of the stored shares. The nodes run rep3 only, the tag
+/// keeps shares of other protocols from being read as rep3 shares.
+const PROTOCOL: &str = "rep3";
+
 pub(super) struct DbStore {
     pool: PgPool,
+    cipher: StorageCipher,
+}
+
+/// A request id reserved by a running request. Dropping it without storing
+/// a response releases the id, so the request can be retried after it failed
+/// or the caller cancelled it.
+pub(crate) struct ReservedResponse {
+    pool: PgPool,
+    method: String,
+    request_id: String,
+    stored: bool,
 }
 
 #[derive(Default, FromRow)]
 struct RootRandomnessSerialized {
+    protocol: String,
+    key_version: i32,
     seed: Vec<u8>,
     seed_r: Vec<u8>,
     seed_c: Vec<u8>,
@@ -26,6 +221,47 @@ pub(crate) struct InitStateSerialized {
     pub(crate) game_state_c: Vec<u8>,
 }
 
+#[derive(Default, FromRow)]
+struct GameStateSerialized {
+    protocol: String,
+    key_version: i32,
+    state: Vec<u8>,
+    prng_state: Vec<u8>,
+    prng_position: Vec<u8>,
+    winning_door: Vec<u8>,
+    chosen_door: Vec<u8>,
+    open_door: Vec<u8>,
+    game_state_r: Vec<u8>,
+    game_state_c: Vec<u8>,
+}
+
+#[derive(Default, FromRow)]
+struct NullifyingKeySerialized {
+    protocol: String,
+    key_version: i32,
+    sk: Vec<u8>,
+    pk: Vec<u8>,
+}
+
+#[derive(Default)]
+pub(crate) struct RevealDoorSerialized {
+    pub(crate) proof: Vec<u8>,
+    pub(crate) game_state_c: Vec<u8>,
+    pub(crate) opened_door: Vec<u8>,
+    pub(crate) opened_door_r: Vec<u8>,
+    pub(crate) opened_door_c: Vec<u8>,
+    pub(crate) game_state_nullifier: Vec<u8>,
+}
+
+#[derive(Default)]
+pub(crate) struct FinishGameSerialized {
+    pub(crate) proof: Vec<u8>,
+    pub(crate) win: Vec<u8>,
+    pub(crate) win_r: Vec<u8>,
+    pub(crate) win_c: Vec<u8>,
+    pub(crate) game_state_nullifier: Vec<u8>,
+}
+
 impl RootRandomnessSerialized {
     fn new() -> Self {
         Self::default()
@@ -38,6 +274,24 @@ impl InitStateSerialized {
     }
 }
 
+impl GameStateSerialized {
+    fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl RevealDoorSerialized {
+    fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl FinishGameSerialized {
+    fn new() -> Self {
+        Self::default()
+    }
+}
+
 impl TryFrom<RootRandomness> for RootRandomnessSerialized {
     type Error = eyre::Report;
     fn try_from(value: RootRandomness) -> eyre::Result<Self> {
@@ -49,25 +303,23 @@ impl TryFrom<RootRandomness> for RootRandomnessSerialized {
     }
 }
 
-impl From<RootRandomnessSerialized> for RootRandomness {
-    fn from(value: RootRandomnessSerialized) -> Self {
-        let seed = ArithmeticShare::deserialize_uncompressed(value.seed.as_slice())
-            .expect("correctly in DB");
-        let seed_r = ArithmeticShare::deserialize_uncompressed(value.seed_r.as_slice())
-            .expect("correctly in DB");
-        let seed_c = ark_bn254::Fr::deserialize_uncompressed(value.seed_c.as_slice())
-            .expect("correctly in DB");
-        Self {
+impl TryFrom<RootRandomnessSerialized> for RootRandomness {
+    type Error = eyre::Report;
+    fn try_from(value: RootRandomnessSerialized) -> eyre::Result<Self> {
+        let seed = ArithmeticShare::deserialize_uncompressed(value.seed.as_slice())?;
+        let seed_r = ArithmeticShare::deserialize_uncompressed(value.seed_r.as_slice())?;
+        let seed_c = ark_bn254::Fr::deserialize_uncompressed(value.seed_c.as_slice())?;
+        Ok(Self {
             seed_c,
             seed,
             seed_r,
-        }
+        })
     }
 }
 
-impl TryFrom<InitState> for InitStateSerialized {
+impl TryFrom<&InitState> for InitStateSerialized {
     type Error = eyre::Report;
-    fn try_from(value: InitState) -> eyre::Result<Self> {
+    fn try_from(value: &InitState) -> eyre::Result<Self> {
         let mut state = InitStateSerialized::new();
         state.proof = value.proof.to_buffer();
         value
@@ -80,54 +332,1018 @@ impl TryFrom<InitState> for InitStateSerialized {
     }
 }
 
+impl TryFrom<&GameState> for GameStateSerialized {
+    type Error = eyre::Report;
+    fn try_from(value: &GameState) -> eyre::Result<Self> {
+        let mut state = GameStateSerialized::new();
+        let game_state = &value.game_state;
+        game_state.state.serialize_uncompressed(&mut state.state)?;
+        game_state
+            .prng_state
+            .serialize_uncompressed(&mut state.prng_state)?;
+        game_state
+            .prng_position
+            .serialize_uncompressed(&mut state.prng_position)?;
+        game_state
+            .winning_door
+            .serialize_uncompressed(&mut state.winning_door)?;
+        game_state
+            .chosen_door
+            .serialize_uncompressed(&mut state.chosen_door)?;
+        game_state
+            .open_door
+            .serialize_uncompressed(&mut state.open_door)?;
+        value
+            .game_state_r
+            .serialize_uncompressed(&mut state.game_state_r)?;
+        value
+            .game_state_c
+            .serialize_uncompressed(&mut state.game_state_c)?;
+        Ok(state)
+    }
+}
+
+impl TryFrom<GameStateSerialized> for GameState {
+    type Error = eyre::Report;
+    fn try_from(value: GameStateSerialized) -> eyre::Result<Self> {
+        let deserialize =
+            |bytes: Vec<u8>| ArithmeticShare::deserialize_uncompressed(bytes.as_slice());
+        let prng_state =
+            <[ArithmeticShare; 4]>::deserialize_uncompressed(value.prng_state.as_slice())?;
+        let game_state = SharedGameState {
+            state: deserialize(value.state)?,
+            prng_state,
+            prng_position: deserialize(value.prng_position)?,
+            winning_door: deserialize(value.winning_door)?,
+            chosen_door: deserialize(value.chosen_door)?,
+            open_door: deserialize(value.open_door)?,
+        };
+        let game_state_r = deserialize(value.game_state_r)?;
+        let game_state_c = ark_bn254::Fr::deserialize_uncompressed(value.game_state_c.as_slice())?;
+        Ok(Self {
+            game_state,
+            game_state_r,
+            game_state_c,
+        })
+    }
+}
+
+impl TryFrom<&NullifyingKey> for NullifyingKeySerialized {
+    type Error = eyre::Report;
+    fn try_from(value: &NullifyingKey) -> eyre::Result<Self> {
+        let mut key = NullifyingKeySerialized::default();
+        value.sk.serialize_uncompressed(&mut key.sk)?;
+        value.pk.serialize_uncompressed(&mut key.pk)?;
+        Ok(key)
+    }
+}
+
+impl TryFrom<NullifyingKeySerialized> for NullifyingKey {
+    type Error = eyre::Report;
+    fn try_from(value: NullifyingKeySerialized) -> eyre::Result<Self> {
+        let sk = ArithmeticShare::deserialize_uncompressed(value.sk.as_slice())?;
+        let pk =
+            <(ark_bn254::Fr, ark_bn254::Fr, bool)>::deserialize_uncompressed(value.pk.as_slice())?;
+        Ok(Self { sk, pk })
+    }
+}
+
+impl TryFrom<&RevealDoorState> for RevealDoorSerialized {
+    type Error = eyre::Report;
+    fn try_from(value: &RevealDoorState) -> eyre::Result<Self> {
+        let mut state = RevealDoorSerialized::new();
+        state.proof = value.proof.to_buffer();
+        value
+            .game_state
+            .game_state_c
+            .serialize_uncompressed(&mut state.game_state_c)?;
+        value
+            .opened_door
+            .serialize_uncompressed(&mut state.opened_door)?;
+        value
+            .opened_door_r
+            .serialize_uncompressed(&mut state.opened_door_r)?;
+        value
+            .opened_door_c
+            .serialize_uncompressed(&mut state.opened_door_c)?;
+        value
+            .game_state_nullifier
+            .serialize_uncompressed(&mut state.game_state_nullifier)?;
+        Ok(state)
+    }
+}
+
+impl TryFrom<FinishGameState> for FinishGameSerialized {
+    type Error = eyre::Report;
+    fn try_from(value: FinishGameState) -> eyre::Result<Self> {
+        let mut state = FinishGameSerialized::new();
+        state.proof = value.proof.to_buffer();
+        value.win.serialize_uncompressed(&mut state.win)?;
+        value.win_r.serialize_uncompressed(&mut state.win_r)?;
+        value.win_c.serialize_uncompressed(&mut state.win_c)?;
+        value
+            .game_state_nullifier
+            .serialize_uncompressed(&mut state.game_state_nullifier)?;
+        Ok(state)
+    }
+}
+
+impl ReservedResponse {
+    /// Stores the response of the request, repeated requests get it from now
+    /// on.
+    pub(crate) async fn store(mut self, response: &[u8]) -> eyre::Result<()> {
+        sqlx::query("UPDATE rpc_response SET response = $3 WHERE method = $1 AND request_id = $2")
+            .bind(&self.method)
+            .bind(&self.request_id)
+            .bind(response)
+            .execute(&self.pool)
+            .await?;
+        self.stored = true;
+        Ok(())
+    }
+}
+
+impl Drop for ReservedResponse {
+    fn drop(&mut self) {
+        if self.stored {
+            return;
+        }
+        let pool = self.pool.clone();
+        let method = std::mem::take(&mut self.method);
+        let request_id = std::mem::take(&mut self.request_id);
+        tokio::spawn(async move {
+            let released = sqlx::query(
+                "DELETE FROM rpc_response WHERE method = $1 AND request_id = $2 AND response IS NULL",
+            )
+            .bind(&method)
+            .bind(&request_id)
+            .execute(&pool)
+            .await;
+            if let Err(err) = released {
+                tracing::error!("cannot release {method} request {request_id}: {err:#}");
+            }
+        });
+    }
+}
+
 impl DbStore {
     pub(super) async fn init(config: &NodeConfig) -> eyre::Result<DbStore> {
         tracing::debug!("connecting to {}", config.postgres_url);
         let pool = PgPoolOptions::new().connect(&config.postgres_url).await?;
         MIGRATOR.run(&pool).await.expect("Couldn't migrate db");
-        Ok(DbStore { pool })
+        let db_store = DbStore {
+            pool,
+            cipher: StorageCipher::init(config)?,
+        };
+        db_store.reencrypt(config.migrate_plaintext_shares).await?;
+        db_store.recover_jobs().await?;
+        Ok(db_store)
     }
 
-    pub(crate) async fn store_root_rand(&self, root_rand: RootRandomness) -> eyre::Result<Vec<u8>> {
-        let serialized = RootRandomnessSerialized::try_from(root_rand)?;
-        // for simplicity we just delete the old randomness
-        sqlx::query("TRUNCATE TABLE root_rand")
+    /// Encrypts all shares stored with an old key version with the current
+    /// key. Shares stored in plaintext are only encrypted if
+    /// `migrate_plaintext` is set, otherwise the node refuses to start.
+    async fn reencrypt(&self, migrate_plaintext: bool) -> eyre::Result<()> {
+        let mut stale = 0;
+        let mut plaintext = 0;
+        for columns in &TABLES {
+            let query = format!(
+                "SELECT COUNT(*) FILTER (WHERE key_version <> $1), COUNT(*) FILTER (WHERE key_version = $2) FROM {}",
+                columns.name
+            );
+            let (table_stale, table_plaintext) = sqlx::query_as::<_, (i64, i64)>(&query)
+                .bind(self.cipher.version())
+                .bind(StorageCipher::PLAINTEXT)
+                .fetch_one(&self.pool)
+                .await?;
+            stale += table_stale;
+            plaintext += table_plaintext;
+        }
+        if plaintext > 0 && !migrate_plaintext {
+            eyre::bail!(
+                "{plaintext} rows store shares in plaintext, run the node once with --migrate-plaintext-shares to encrypt them"
+            );
+        }
+        if stale > 0 {
+            tracing::info!(
+                "re-encrypting shares of {stale} rows ({plaintext} in plaintext) with key version {}",
+                self.cipher.version()
+            );
+            let rows = self.read_rows(migrate_plaintext).await?;
+            let mut tx = self.pool.begin().await?;
+            self.update_shares(&mut tx, &rows).await?;
+            tx.commit().await?;
+        }
+        let inputs = sqlx::query_as::<_, (Uuid, String, i32, Vec<u8>)>(
+            "SELECT game_id, phase, key_version, input FROM mpc_job WHERE input IS NOT NULL AND key_version <> $1",
+        )
+        .bind(self.cipher.version())
+        .fetch_all(&self.pool)
+        .await?;
+        for (game_id, phase, key_version, mut input) in inputs {
+            self.open("mpc_job", &phase, game_id, key_version, &mut input)?;
+            sqlx::query(
+                "UPDATE mpc_job SET key_version = $3, input = $4 WHERE game_id = $1 AND phase = $2",
+            )
+            .bind(game_id)
+            .bind(&phase)
+            .bind(self.cipher.version())
+            .bind(self.seal("mpc_job", &phase, game_id, &input)?)
             .execute(&self.pool)
             .await?;
-        sqlx::query("INSERT INTO root_rand (seed, seed_r, seed_c) VALUES ($1, $2, $3)")
-            .bind(serialized.seed.as_slice())
-            .bind(serialized.seed_r.as_slice())
-            .bind(serialized.seed_c.as_slice())
+        }
+        Ok(())
+    }
+
+    /// Rolls back the jobs a crash interrupted. Their results were never
+    /// stored, as a job completes in the transaction storing its result.
+    /// The other nodes may have completed them, which the reconcile of the
+    /// orchestration resolves. The request ids the interrupted requests
+    /// reserved are released, so the requests can be retried.
+    async fn recover_jobs(&self) -> eyre::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let interrupted = sqlx::query_as::<_, (Uuid, String)>(
+            "UPDATE mpc_job SET status = $2, input = NULL, updated_at = now() WHERE status = $1 RETURNING game_id, phase",
+        )
+        .bind(JobStatus::Running.tag())
+        .bind(JobStatus::RolledBack.tag())
+        .fetch_all(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM rpc_response WHERE response IS NULL")
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        for (game_id, phase) in interrupted {
+            tracing::warn!("rolled back interrupted job {phase} of game {game_id}");
+        }
+        Ok(())
+    }
+
+    /// Records the job of a step before its MPC starts. Steps replacing the
+    /// game state keep the old one as input, so they can be rolled back.
+    /// Only a step that never ran or was rolled back can start again.
+    pub(crate) async fn begin_job(
+        &self,
+        game_id: Uuid,
+        phase: GamePhase,
+        input: Option<&GameState>,
+    ) -> eyre::Result<()> {
+        let input = input
+            .map(|game_state| {
+                self.seal(
+                    "mpc_job",
+                    phase.tag(),
+                    game_id,
+                    &serialize_game_state(game_state)?,
+                )
+            })
+            .transpose()?;
+        let begun = sqlx::query(
+            "INSERT INTO mpc_job (game_id, phase, status, key_version, input, output) VALUES ($1, $2, $3, $4, $5, NULL)
+            ON CONFLICT (game_id, phase) DO UPDATE SET status = EXCLUDED.status, key_version = EXCLUDED.key_version, input = EXCLUDED.input, output = NULL, updated_at = now() WHERE mpc_job.status = $6",
+        )
+        .bind(game_id)
+        .bind(phase.tag())
+        .bind(JobStatus::Running.tag())
+        .bind(self.cipher.version())
+        .bind(input)
+        .bind(JobStatus::RolledBack.tag())
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        if begun == 0 {
+            return Err(DuplicateJob { game_id, phase }.into());
+        }
+        Ok(())
+    }
+
+    /// Rolls back the job of a step that failed before storing its result.
+    pub(crate) async fn fail_job(&self, game_id: Uuid, phase: GamePhase) -> eyre::Result<()> {
+        sqlx::query(
+            "UPDATE mpc_job SET status = $4, input = NULL, updated_at = now() WHERE game_id = $1 AND phase = $2 AND status = $3",
+        )
+        .bind(game_id)
+        .bind(phase.tag())
+        .bind(JobStatus::Running.tag())
+        .bind(JobStatus::RolledBack.tag())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Completes the job with the transaction storing the result of its
+    /// step.
+    async fn complete_job(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        game_id: Uuid,
+        phase: GamePhase,
+        output: &[u8],
+    ) -> eyre::Result<()> {
+        sqlx::query(
+            "UPDATE mpc_job SET status = $3, output = $4, updated_at = now() WHERE game_id = $1 AND phase = $2",
+        )
+        .bind(game_id)
+        .bind(phase.tag())
+        .bind(JobStatus::Completed.tag())
+        .bind(output)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Loads the jobs, ordered by game and phase.
+    pub(crate) async fn load_jobs(&self) -> eyre::Result<Vec<Job>> {
+        sqlx::query_as::<_, (Uuid, String, String, Option<Vec<u8>>, i64)>(
+            "SELECT game_id, phase, status, output, EXTRACT(EPOCH FROM now() - updated_at)::BIGINT FROM mpc_job ORDER BY game_id, phase",
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|(game_id, phase, status, output, age_secs)| {
+            Ok(Job {
+                game_id,
+                phase: GamePhase::from_tag(&phase)?,
+                status: JobStatus::from_tag(&status)?,
+                output: output.unwrap_or_default(),
+                age: Duration::from_secs(age_secs.max(0) as u64),
+            })
+        })
+        .collect()
+    }
+
+    /// Undoes the completed step of a game that moved it to `phase`, which
+    /// has to be the last step of the game.
+    pub(crate) async fn rollback_job(&self, game_id: Uuid, phase: GamePhase) -> eyre::Result<()> {
+        let cannot = |reason| CannotRollBack {
+            game_id,
+            phase,
+            reason,
+        };
+        let mut tx = self.pool.begin().await?;
+        let job = sqlx::query_as::<_, (String, i32, Option<Vec<u8>>)>(
+            "SELECT status, key_version, input FROM mpc_job WHERE game_id = $1 AND phase = $2 FOR UPDATE",
+        )
+        .bind(game_id)
+        .bind(phase.tag())
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((status, key_version, input)) = job else {
+            return Err(cannot("the step never ran or the shares were refreshed since").into());
+        };
+        if JobStatus::from_tag(&status)? != JobStatus::Completed {
+            return Err(cannot("the step is not completed").into());
+        }
+        let reverted = match phase.previous() {
+            None => sqlx::query("DELETE FROM root_rand WHERE game_id = $1 AND phase = $2")
+                .bind(game_id)
+                .bind(phase.tag()),
+            Some(previous) => {
+                sqlx::query("UPDATE root_rand SET phase = $3 WHERE game_id = $1 AND phase = $2")
+                    .bind(game_id)
+                    .bind(phase.tag())
+                    .bind(previous.tag())
+            }
+        }
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if reverted == 0 {
+            return Err(cannot("it is not the last step of the game").into());
+        }
+        let previous_state = match phase {
+            GamePhase::OpenedDoor | GamePhase::Done => {
+                let mut input = input.ok_or_else(|| cannot("its input is missing"))?;
+                self.open("mpc_job", phase.tag(), game_id, key_version, &mut input)?;
+                Some(deserialize_game_state(&input)?)
+            }
+            GamePhase::Sampled | GamePhase::KeyGenerated | GamePhase::Started => None,
+        };
+        let tables: &[&str] = match phase {
+            GamePhase::Sampled => &[],
+            GamePhase::KeyGenerated => &["nullifying_key"],
+            GamePhase::Started => &["monty_hall_game_state", "monty_hall_game_init_state"],
+            GamePhase::OpenedDoor => &["monty_hall_game_opened_door"],
+            GamePhase::Done => &["monty_hall_game_result"],
+        };
+        for table in tables {
+            sqlx::query(&format!("DELETE FROM {table} WHERE game_id = $1"))
+                .bind(game_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        if let Some(game_state) = previous_state {
+            self.store_game_state(&mut tx, game_id, &game_state).await?;
+        }
+        sqlx::query(
+            "UPDATE mpc_job SET status = $3, input = NULL, output = NULL, updated_at = now() WHERE game_id = $1 AND phase = $2",
+        )
+        .bind(game_id)
+        .bind(phase.tag())
+        .bind(JobStatus::RolledBack.tag())
+        .execute(&mut *tx)
+        .await?;
+        // a repeated request runs the step again instead of answering with
+        // the undone result
+        sqlx::query("DELETE FROM rpc_response WHERE game_id = $1 AND phase = $2")
+            .bind(game_id)
+            .bind(phase.tag())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        tracing::info!("rolled back {phase} of game {game_id}");
+        Ok(())
+    }
+
+    /// Reserves a request id for a request with a pending row, unless
+    /// another request with the id ran or runs already. Requests of game
+    /// steps are stored with the step, see [`DbStore::rollback_job`].
+    pub(crate) async fn reserve_response(
+        &self,
+        method: &str,
+        request_id: &str,
+        request_hash: &[u8],
+        step: Option<(Uuid, GamePhase)>,
+    ) -> eyre::Result<Reservation> {
+        loop {
+            let reserved = sqlx::query(
+                "INSERT INTO rpc_response (method, request_id, request_hash, game_id, phase, response) VALUES ($1, $2, $3, $4, $5, NULL)
+                ON CONFLICT (method, request_id) DO NOTHING",
+            )
+            .bind(method)
+            .bind(request_id)
+            .bind(request_hash)
+            .bind(step.map(|(game_id, _)| game_id))
+            .bind(step.map(|(_, phase)| phase.tag()))
             .execute(&self.pool)
+            .await?
+            .rows_affected();
+            if reserved == 1 {
+                return Ok(Reservation::Reserved(ReservedResponse {
+                    pool: self.pool.clone(),
+                    method: method.to_owned(),
+                    request_id: request_id.to_owned(),
+                    stored: false,
+                }));
+            }
+            let row = sqlx::query_as::<_, (Vec<u8>, Option<Vec<u8>>)>(
+                "SELECT request_hash, response FROM rpc_response WHERE method = $1 AND request_id = $2",
+            )
+            .bind(method)
+            .bind(request_id)
+            .fetch_optional(&self.pool)
             .await?;
+            match row {
+                Some((request_hash, None)) => return Ok(Reservation::Pending { request_hash }),
+                Some((request_hash, Some(response))) => {
+                    return Ok(Reservation::Done(StoredResponse {
+                        request_hash,
+                        response,
+                    }));
+                }
+                // the request holding the id failed in between
+                None => continue,
+            }
+        }
+    }
+
+    /// Encrypts the share column `column` of the row of `game_id`.
+    fn seal(
+        &self,
+        table: &str,
+        column: &str,
+        game_id: Uuid,
+        plaintext: &[u8],
+    ) -> eyre::Result<Vec<u8>> {
+        self.cipher
+            .encrypt(plaintext, format!("{table}.{column}.{game_id}").as_bytes())
+    }
+
+    /// Decrypts the share column `column` of the row of `game_id` in place.
+    fn open(
+        &self,
+        table: &str,
+        column: &str,
+        game_id: Uuid,
+        key_version: i32,
+        stored: &mut Vec<u8>,
+    ) -> eyre::Result<()> {
+        *stored = self.cipher.decrypt(
+            key_version,
+            stored,
+            format!("{table}.{column}.{game_id}").as_bytes(),
+        )?;
+        Ok(())
+    }
 
+    /// Shares of another protocol cannot be used by this node.
+    fn check_protocol(&self, game_id: Uuid, stored: &str) -> eyre::Result<()> {
+        if stored != PROTOCOL {
+            eyre::bail!(
+                "shares of game {game_id} are {stored} shares but the node runs {PROTOCOL}"
+            );
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn store_root_rand(
+        &self,
+        game_id: Uuid,
+        root_rand: RootRandomness,
+    ) -> eyre::Result<Vec<u8>> {
+        let serialized = RootRandomnessSerialized::try_from(root_rand)?;
+        let mut tx = self.pool.begin().await?;
+        let inserted = sqlx::query(
+            "INSERT INTO root_rand (game_id, protocol, key_version, phase, seed, seed_r, seed_c) VALUES ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT (game_id) DO NOTHING",
+        )
+        .bind(game_id)
+        .bind(PROTOCOL)
+        .bind(self.cipher.version())
+        .bind(GamePhase::Sampled.tag())
+        .bind(self.seal("root_rand", "seed", game_id, &serialized.seed)?)
+        .bind(self.seal("root_rand", "seed_r", game_id, &serialized.seed_r)?)
+        .bind(serialized.seed_c.as_slice())
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if inserted == 0 {
+            let phase = self.load_phase(game_id).await?;
+            return Err(InvalidTransition {
+                game_id,
+                phase,
+                next: GamePhase::Sampled,
+            }
+            .into());
+        }
+        self.complete_job(&mut tx, game_id, GamePhase::Sampled, &serialized.seed_c)
+            .await?;
+        tx.commit().await?;
         Ok(serialized.seed_c)
     }
 
-    pub(crate) async fn load_root_rand(&self) -> eyre::Result<RootRandomness> {
+    pub(crate) async fn load_phase(&self, game_id: Uuid) -> eyre::Result<Option<GamePhase>> {
+        fetch_phase(&self.pool, game_id).await
+    }
+
+    /// Checks that the game may move to `next`, before running the step
+    /// that moves it there.
+    pub(crate) async fn check_phase(&self, game_id: Uuid, next: GamePhase) -> eyre::Result<()> {
+        let phase = self.load_phase(game_id).await?;
+        GamePhase::check_transition(game_id, phase, next)?;
+        Ok(())
+    }
+
+    /// Moves the game to `next` with the transaction storing the result of
+    /// the step. Fails if another step moved the game in the meantime, the
+    /// updated row stays locked until the transaction ends.
+    async fn advance_phase(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        game_id: Uuid,
+        next: GamePhase,
+    ) -> eyre::Result<()> {
+        let previous = next.previous().map(GamePhase::tag);
+        let updated =
+            sqlx::query("UPDATE root_rand SET phase = $3 WHERE game_id = $1 AND phase = $2")
+                .bind(game_id)
+                .bind(previous)
+                .bind(next.tag())
+                .execute(&mut **tx)
+                .await?
+                .rows_affected();
+        if updated == 0 {
+            let phase = fetch_phase(&mut **tx, game_id).await?;
+            return Err(InvalidTransition {
+                game_id,
+                phase,
+                next,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn load_root_rand(
+        &self,
+        game_id: Uuid,
+    ) -> eyre::Result<Option<RootRandomness>> {
         let row = sqlx::query_as::<_, RootRandomnessSerialized>(
-            "SELECT seed, seed_r, seed_c FROM root_rand ",
+            "SELECT protocol, key_version, seed, seed_r, seed_c FROM root_rand WHERE game_id = $1",
         )
-        .fetch_one(&self.pool)
+        .bind(game_id)
+        .fetch_optional(&self.pool)
         .await?;
-        Ok(RootRandomness::from(row))
+        let Some(mut row) = row else {
+            return Ok(None);
+        };
+        self.check_protocol(game_id, &row.protocol)?;
+        let version = row.key_version;
+        self.open("root_rand", "seed", game_id, version, &mut row.seed)?;
+        self.open("root_rand", "seed_r", game_id, version, &mut row.seed_r)?;
+        let value = RootRandomness::try_from(row)
+            .with_context(|| format!("stored root randomness of game {game_id} is corrupt"))?;
+        Ok(Some(value))
     }
 
     pub(crate) async fn init_monty_hall(
         &self,
+        game_id: Uuid,
         init_state: InitState,
     ) -> eyre::Result<InitStateSerialized> {
-        let serialized = InitStateSerialized::try_from(init_state)?;
-        // for simplicity we just delete the old randomness
-        sqlx::query("TRUNCATE TABLE monty_hall_game ")
-            .execute(&self.pool)
+        let serialized = InitStateSerialized::try_from(&init_state)?;
+        let game_state = GameState {
+            game_state: init_state.game_state,
+            game_state_r: init_state.game_state_r,
+            game_state_c: init_state.game_state_c,
+        };
+        let mut tx = self.pool.begin().await?;
+        self.advance_phase(&mut tx, game_id, GamePhase::Started)
             .await?;
-        sqlx::query("INSERT INTO monty_hall_game_init_state (proof, game_state_r, game_state_c) VALUES ($1, $2, $3)")
+        self.complete_job(
+            &mut tx,
+            game_id,
+            GamePhase::Started,
+            &serialized.game_state_c,
+        )
+        .await?;
+        sqlx::query("INSERT INTO monty_hall_game_init_state (game_id, protocol, key_version, proof, game_state_r, game_state_c) VALUES ($1, $2, $3, $4, $5, $6)")
+            .bind(game_id)
+            .bind(PROTOCOL)
+            .bind(self.cipher.version())
             .bind(serialized.proof.as_slice())
-            .bind(serialized.game_state_r.as_slice())
+            .bind(self.seal("monty_hall_game_init_state", "game_state_r", game_id, &serialized.game_state_r)?)
             .bind(serialized.game_state_c.as_slice())
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
+        self.store_game_state(&mut tx, game_id, &game_state).await?;
+        tx.commit().await?;
         Ok(serialized)
     }
+
+    pub(crate) async fn load_game_state(&self, game_id: Uuid) -> eyre::Result<Option<GameState>> {
+        let row = sqlx::query_as::<_, GameStateSerialized>(
+            "SELECT protocol, key_version, state, prng_state, prng_position, winning_door, chosen_door, open_door, game_state_r, game_state_c FROM monty_hall_game_state WHERE game_id = $1",
+        )
+        .bind(game_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(mut row) = row else {
+            return Ok(None);
+        };
+        self.check_protocol(game_id, &row.protocol)?;
+        let version = row.key_version;
+        for (column, stored) in [
+            ("state", &mut row.state),
+            ("prng_state", &mut row.prng_state),
+            ("prng_position", &mut row.prng_position),
+            ("winning_door", &mut row.winning_door),
+            ("chosen_door", &mut row.chosen_door),
+            ("open_door", &mut row.open_door),
+            ("game_state_r", &mut row.game_state_r),
+        ] {
+            self.open("monty_hall_game_state", column, game_id, version, stored)?;
+        }
+        let value = GameState::try_from(row)
+            .with_context(|| format!("stored game state of game {game_id} is corrupt"))?;
+        Ok(Some(value))
+    }
+
+    pub(crate) async fn store_nullifying_key(
+        &self,
+        game_id: Uuid,
+        nullifying_key: &NullifyingKey,
+    ) -> eyre::Result<()> {
+        let serialized = NullifyingKeySerialized::try_from(nullifying_key)?;
+        let mut tx = self.pool.begin().await?;
+        self.advance_phase(&mut tx, game_id, GamePhase::KeyGenerated)
+            .await?;
+        self.complete_job(&mut tx, game_id, GamePhase::KeyGenerated, &serialized.pk)
+            .await?;
+        sqlx::query(
+            "INSERT INTO nullifying_key (game_id, protocol, key_version, sk, pk) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(game_id)
+        .bind(PROTOCOL)
+        .bind(self.cipher.version())
+        .bind(self.seal("nullifying_key", "sk", game_id, &serialized.sk)?)
+        .bind(serialized.pk.as_slice())
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub(crate) async fn load_nullifying_key(
+        &self,
+        game_id: Uuid,
+    ) -> eyre::Result<Option<NullifyingKey>> {
+        let row = sqlx::query_as::<_, NullifyingKeySerialized>(
+            "SELECT protocol, key_version, sk, pk FROM nullifying_key WHERE game_id = $1",
+        )
+        .bind(game_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(mut row) = row else {
+            return Ok(None);
+        };
+        self.check_protocol(game_id, &row.protocol)?;
+        self.open(
+            "nullifying_key",
+            "sk",
+            game_id,
+            row.key_version,
+            &mut row.sk,
+        )?;
+        let value = NullifyingKey::try_from(row)
+            .with_context(|| format!("stored nullifying key of game {game_id} is corrupt"))?;
+        Ok(Some(value))
+    }
+
+    pub(crate) async fn reveal_door(
+        &self,
+        game_id: Uuid,
+        reveal_door: RevealDoorState,
+    ) -> eyre::Result<RevealDoorSerialized> {
+        let serialized = RevealDoorSerialized::try_from(&reveal_door)?;
+        let mut tx = self.pool.begin().await?;
+        self.advance_phase(&mut tx, game_id, GamePhase::OpenedDoor)
+            .await?;
+        self.complete_job(
+            &mut tx,
+            game_id,
+            GamePhase::OpenedDoor,
+            &serialized.opened_door_c,
+        )
+        .await?;
+        sqlx::query("INSERT INTO monty_hall_game_opened_door (game_id, protocol, key_version, proof, opened_door, opened_door_r, opened_door_c, game_state_nullifier) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)")
+            .bind(game_id)
+            .bind(PROTOCOL)
+            .bind(self.cipher.version())
+            .bind(serialized.proof.as_slice())
+            .bind(self.seal("monty_hall_game_opened_door", "opened_door", game_id, &serialized.opened_door)?)
+            .bind(self.seal("monty_hall_game_opened_door", "opened_door_r", game_id, &serialized.opened_door_r)?)
+            .bind(serialized.opened_door_c.as_slice())
+            .bind(serialized.game_state_nullifier.as_slice())
+            .execute(&mut *tx)
+            .await?;
+        self.store_game_state(&mut tx, game_id, &reveal_door.game_state)
+            .await?;
+        tx.commit().await?;
+        Ok(serialized)
+    }
+
+    pub(crate) async fn finish_game(
+        &self,
+        game_id: Uuid,
+        finish_game: FinishGameState,
+    ) -> eyre::Result<FinishGameSerialized> {
+        let serialized = FinishGameSerialized::try_from(finish_game)?;
+        let mut tx = self.pool.begin().await?;
+        self.advance_phase(&mut tx, game_id, GamePhase::Done)
+            .await?;
+        self.complete_job(&mut tx, game_id, GamePhase::Done, &serialized.win_c)
+            .await?;
+        sqlx::query("INSERT INTO monty_hall_game_result (game_id, protocol, key_version, proof, win, win_r, win_c, game_state_nullifier) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)")
+            .bind(game_id)
+            .bind(PROTOCOL)
+            .bind(self.cipher.version())
+            .bind(serialized.proof.as_slice())
+            .bind(self.seal("monty_hall_game_result", "win", game_id, &serialized.win)?)
+            .bind(self.seal("monty_hall_game_result", "win_r", game_id, &serialized.win_r)?)
+            .bind(serialized.win_c.as_slice())
+            .bind(serialized.game_state_nullifier.as_slice())
+            .execute(&mut *tx)
+            .await?;
+        // the game is over and its last game state is nullified
+        sqlx::query("DELETE FROM monty_hall_game_state WHERE game_id = $1")
+            .bind(game_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(serialized)
+    }
+
+    /// Loads the rows of all tables keyed by the game id, table by table
+    /// and ordered by game id, so every party loads them in the same order.
+    pub(crate) async fn load_rows(&self) -> eyre::Result<Vec<StoredRow>> {
+        self.read_rows(false).await
+    }
+
+    /// Loads the rows like [`DbStore::load_rows`]. Shares stored in
+    /// plaintext are only read if `migrate_plaintext` is set, to encrypt
+    /// them.
+    async fn read_rows(&self, migrate_plaintext: bool) -> eyre::Result<Vec<StoredRow>> {
+        let mut rows = Vec::new();
+        for (table, columns) in TABLES.iter().enumerate() {
+            let query = format!(
+                "SELECT game_id, protocol, key_version, {} FROM {} ORDER BY game_id",
+                columns.all().join(", "),
+                columns.name
+            );
+            for row in sqlx::query(&query).fetch_all(&self.pool).await? {
+                let game_id = row.try_get::<Uuid, _>("game_id")?;
+                self.check_protocol(game_id, &row.try_get::<String, _>("protocol")?)?;
+                let key_version = row.try_get::<i32, _>("key_version")?;
+                let public = columns
+                    .public
+                    .iter()
+                    .map(|column| row.try_get::<Vec<u8>, _>(*column))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let shares = columns
+                    .shares
+                    .iter()
+                    .map(|column| {
+                        let mut stored = row.try_get::<Vec<u8>, _>(*column)?;
+                        if !(migrate_plaintext && key_version == StorageCipher::PLAINTEXT) {
+                            self.open(columns.name, column, game_id, key_version, &mut stored)?;
+                        }
+                        deserialize_shares(&stored)
+                    })
+                    .collect::<eyre::Result<Vec<_>>>()?;
+                rows.push(StoredRow {
+                    table,
+                    game_id,
+                    public,
+                    shares,
+                });
+            }
+        }
+        Ok(rows)
+    }
+
+    /// Loads the responses of the requests that ran, ordered by method and
+    /// request id.
+    pub(crate) async fn load_responses(&self) -> eyre::Result<Vec<ResponseRow>> {
+        let rows = sqlx::query_as::<_, (String, String, Vec<u8>, Option<Uuid>, Option<String>, Vec<u8>)>(
+            "SELECT method, request_id, request_hash, game_id, phase, response FROM rpc_response WHERE response IS NOT NULL ORDER BY method, request_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(
+                |(method, request_id, request_hash, game_id, phase, response)| ResponseRow {
+                    method,
+                    request_id,
+                    request_hash,
+                    game_id,
+                    phase,
+                    response,
+                },
+            )
+            .collect())
+    }
+
+    /// Fails while a job runs. The shares cannot be replaced with a new
+    /// sharing then, the job would store shares of the old one.
+    pub(crate) async fn check_no_running_jobs(&self) -> eyre::Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        Self::no_running_jobs(&mut *conn).await
+    }
+
+    async fn no_running_jobs(conn: &mut sqlx::PgConnection) -> eyre::Result<()> {
+        let running =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM mpc_job WHERE status = $1")
+                .bind(JobStatus::Running.tag())
+                .fetch_one(conn)
+                .await?;
+        if running > 0 {
+            return Err(JobsRunning { running }.into());
+        }
+        Ok(())
+    }
+
+    /// Replaces the shares of the rows with a new sharing, all or nothing,
+    /// unless a job runs. All parties checked that they hold the same rows,
+    /// so there is nothing left to reconcile. The completed and rolled back
+    /// jobs are removed from the log, their inputs hold shares of the old
+    /// sharing.
+    pub(crate) async fn store_shares(&self, rows: &[StoredRow]) -> eyre::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        Self::no_running_jobs(&mut *tx).await?;
+        self.update_shares(&mut tx, rows).await?;
+        sqlx::query("DELETE FROM mpc_job WHERE status IN ($1, $2)")
+            .bind(JobStatus::Completed.tag())
+            .bind(JobStatus::RolledBack.tag())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn update_shares(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        rows: &[StoredRow],
+    ) -> eyre::Result<()> {
+        for row in rows {
+            let columns = &TABLES[row.table];
+            let assignments = columns
+                .shares
+                .iter()
+                .enumerate()
+                .map(|(i, column)| format!("{column} = ${}", i + 3))
+                .collect::<Vec<_>>();
+            let query = format!(
+                "UPDATE {} SET key_version = $2, {} WHERE game_id = $1",
+                columns.name,
+                assignments.join(", ")
+            );
+            let mut query = sqlx::query(&query)
+                .bind(row.game_id)
+                .bind(self.cipher.version());
+            for (column, shares) in columns.shares.iter().zip(&row.shares) {
+                query = query.bind(self.seal(
+                    columns.name,
+                    column,
+                    row.game_id,
+                    &serialize_shares(shares)?,
+                )?);
+            }
+            query.execute(&mut **tx).await?;
+        }
+        Ok(())
+    }
+
+    /// Inserts the rows and the responses into an empty database, all or
+    /// nothing. The phases of the games follow from the inserted rows.
+    pub(crate) async fn insert_rows(
+        &self,
+        rows: &[StoredRow],
+        responses: &[ResponseRow],
+    ) -> eyre::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for row in rows {
+            let columns = &TABLES[row.table];
+            let all = columns.all();
+            let placeholders = (0..all.len() + 3)
+                .map(|i| format!("${}", i + 1))
+                .collect::<Vec<_>>();
+            let query = format!(
+                "INSERT INTO {} (game_id, protocol, key_version, {}) VALUES ({})",
+                columns.name,
+                all.join(", "),
+                placeholders.join(", ")
+            );
+            let mut query = sqlx::query(&query)
+                .bind(row.game_id)
+                .bind(PROTOCOL)
+                .bind(self.cipher.version());
+            for public in &row.public {
+                query = query.bind(public.as_slice());
+            }
+            for (column, shares) in columns.shares.iter().zip(&row.shares) {
+                query = query.bind(self.seal(
+                    columns.name,
+                    column,
+                    row.game_id,
+                    &serialize_shares(shares)?,
+                )?);
+            }
+            query.execute(&mut *tx).await?;
+        }
+        sqlx::query(PHASE_FROM_ROWS).execute(&mut *tx).await?;
+        for response in responses {
+            sqlx::query("INSERT INTO rpc_response (method, request_id, request_hash, game_id, phase, response) VALUES ($1, $2, $3, $4, $5, $6)")
+                .bind(&response.method)
+                .bind(&response.request_id)
+                .bind(response.request_hash.as_slice())
+                .bind(response.game_id)
+                .bind(response.phase.as_deref())
+                .bind(response.response.as_slice())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn store_game_state(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        game_id: Uuid,
+        game_state: &GameState,
+    ) -> eyre::Result<()> {
+        let serialized = GameStateSerialized::try_from(game_state)?;
+        let seal = |column, plaintext: &[u8]| {
+            self.seal("monty_hall_game_state", column, game_id, plaintext)
+        };
+        // the old game state is nullified, so we only keep the current one
+        sqlx::query("INSERT INTO monty_hall_game_state (game_id, protocol, key_version, state, prng_state, prng_position, winning_door, chosen_door, open_door, game_state_r, game_state_c) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (game_id) DO UPDATE SET protocol = EXCLUDED.protocol, key_version = EXCLUDED.key_version, state = EXCLUDED.state, prng_state = EXCLUDED.prng_state, prng_position = EXCLUDED.prng_position, winning_door = EXCLUDED.winning_door, chosen_door = EXCLUDED.chosen_door, open_door = EXCLUDED.open_door, game_state_r = EXCLUDED.game_state_r, game_state_c = EXCLUDED.game_state_c")
+            .bind(game_id)
+            .bind(PROTOCOL)
+            .bind(self.cipher.version())
+            .bind(seal("state", &serialized.state)?)
+            .bind(seal("prng_state", &serialized.prng_state)?)
+            .bind(seal("prng_position", &serialized.prng_position)?)
+            .bind(seal("winning_door", &serialized.winning_door)?)
+            .bind(seal("chosen_door", &serialized.chosen_door)?)
+            .bind(seal("open_door", &serialized.open_door)?)
+            .bind(seal("game_state_r", &serialized.game_state_r)?)
+            .bind(serialized.game_state_c.as_slice())
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
 }
@@ -0,0 +1,103 @@
+use uuid::Uuid;
+
+use crate::jobs::{CannotRollBack, DuplicateJob, JobsRunning};
+use crate::phase::InvalidTransition;
+
+pub(crate) type NodeResult<T> = Result<T, NodeError>;
+
+/// The errors of the gRPC handlers. Every variant maps to the
+/// [`tonic::Status`] code the caller can act upon.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum NodeError {
+    #[error("{0}")]
+    InvalidArgument(String),
+    #[error("no root randomness for game {0}")]
+    NoRootRand(Uuid),
+    #[error("no game state for game {0}")]
+    NoGameState(Uuid),
+    #[error("no nullifying key for game {0}")]
+    NoNullifyingKey(Uuid),
+    #[error(transparent)]
+    InvalidTransition(InvalidTransition),
+    #[error(transparent)]
+    DuplicateJob(DuplicateJob),
+    #[error(transparent)]
+    CannotRollBack(CannotRollBack),
+    #[error(transparent)]
+    JobsRunning(JobsRunning),
+    #[error("database is unavailable: {0:#}")]
+    DbUnavailable(eyre::Report),
+    #[error("our {0} proof does not verify")]
+    InvalidProof(&'static str),
+    #[error("MPC with the other nodes failed: {0:#}")]
+    Mpc(eyre::Report),
+    #[error(transparent)]
+    Internal(#[from] eyre::Report),
+}
+
+impl NodeError {
+    /// Wraps an error of the [`DbStore`](crate::data_store::DbStore). Steps
+    /// out of order, repeated, or not to be undone and shares replaced while
+    /// steps run are reported as such, errors reaching the database as
+    /// unavailable, everything else (e.g. a violated constraint) is internal.
+    pub(crate) fn db(err: eyre::Report) -> Self {
+        let err = match err.downcast::<InvalidTransition>() {
+            Ok(transition) => return Self::InvalidTransition(transition),
+            Err(err) => err,
+        };
+        let err = match err.downcast::<DuplicateJob>() {
+            Ok(duplicate) => return Self::DuplicateJob(duplicate),
+            Err(err) => err,
+        };
+        let err = match err.downcast::<CannotRollBack>() {
+            Ok(rollback) => return Self::CannotRollBack(rollback),
+            Err(err) => err,
+        };
+        let err = match err.downcast::<JobsRunning>() {
+            Ok(running) => return Self::JobsRunning(running),
+            Err(err) => err,
+        };
+        match err.downcast_ref::<sqlx::Error>() {
+            Some(
+                sqlx::Error::Io(_)
+                | sqlx::Error::Tls(_)
+                | sqlx::Error::PoolTimedOut
+                | sqlx::Error::PoolClosed
+                | sqlx::Error::WorkerCrashed,
+            ) => Self::DbUnavailable(err),
+            _ => Self::Internal(err),
+        }
+    }
+}
+
+impl From<NodeError> for tonic::Status {
+    fn from(err: NodeError) -> Self {
+        let message = err.to_string();
+        match err {
+            NodeError::InvalidArgument(_) => tonic::Status::invalid_argument(message),
+            NodeError::NoRootRand(_)
+            | NodeError::NoGameState(_)
+            | NodeError::NoNullifyingKey(_)
+            | NodeError::InvalidTransition(_)
+            | NodeError::DuplicateJob(_)
+            | NodeError::CannotRollBack(_)
+            | NodeError::JobsRunning(_) => tonic::Status::failed_precondition(message),
+            NodeError::DbUnavailable(err) => {
+                tracing::error!("{err:#?}");
+                tonic::Status::unavailable(message)
+            }
+            NodeError::InvalidProof(_) => {
+                tracing::error!("{message}");
+                tonic::Status::internal(message)
+            }
+            NodeError::Mpc(err) => {
+                tracing::error!("{err:#?}");
+                tonic::Status::aborted(message)
+            }
+            NodeError::Internal(err) => {
+                tracing::error!("{err:#?}");
+                tonic::Status::internal(message)
+            }
+        }
+    }
+}
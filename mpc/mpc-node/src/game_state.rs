@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+
+use co_noir::Rep3AcvmType;
+use eyre::Context as _;
+use noirc_artifacts::program::ProgramArtifact;
+
+use crate::mpc::ArithmeticShare;
+
+/// The secret-shared `GameState` of `monty_hall/src/game_state.nr`.
+///
+/// The field order of [`SharedGameState::as_field_array`] mirrors
+/// `PrivateStateTrait::as_field_array`, which is both the order the
+/// commitment is computed over and the order the circuits flatten their
+/// `state` parameter in.
+#[derive(Clone)]
+pub(crate) struct SharedGameState {
+    pub(crate) state: ArithmeticShare,
+    pub(crate) prng_state: [ArithmeticShare; 4],
+    pub(crate) prng_position: ArithmeticShare,
+    pub(crate) winning_door: ArithmeticShare,
+    pub(crate) chosen_door: ArithmeticShare,
+    pub(crate) open_door: ArithmeticShare,
+}
+
+impl SharedGameState {
+    /// The number of field elements of a `GameState`.
+    pub(crate) const N: usize = 9;
+
+    /// The name under which the circuits store a new game state.
+    pub(crate) const PRIVATE_STATE_NAME: &str = "game_state";
+
+    /// The name of the circuit parameter holding the current game state.
+    pub(crate) const PARAMETER_NAME: &str = "state";
+
+    pub(crate) fn as_field_array(&self) -> [ArithmeticShare; Self::N] {
+        let [prng0, prng1, prng2, prng3] = self.prng_state.clone();
+        [
+            self.state.clone(),
+            prng0,
+            prng1,
+            prng2,
+            prng3,
+            self.prng_position.clone(),
+            self.winning_door.clone(),
+            self.chosen_door.clone(),
+            self.open_door.clone(),
+        ]
+    }
+
+    pub(crate) fn from_field_array(fields: [ArithmeticShare; Self::N]) -> Self {
+        let [
+            state,
+            prng0,
+            prng1,
+            prng2,
+            prng3,
+            prng_position,
+            winning_door,
+            chosen_door,
+            open_door,
+        ] = fields;
+        Self {
+            state,
+            prng_state: [prng0, prng1, prng2, prng3],
+            prng_position,
+            winning_door,
+            chosen_door,
+            open_door,
+        }
+    }
+
+    /// Checks that the `state` parameter of the circuit has the layout of a
+    /// `GameState`.
+    pub(crate) fn check_abi(circuit: &ProgramArtifact) -> eyre::Result<()> {
+        let parameter = circuit
+            .abi
+            .parameters
+            .iter()
+            .find(|parameter| parameter.name == Self::PARAMETER_NAME)
+            .with_context(|| format!("circuit has no parameter {}", Self::PARAMETER_NAME))?;
+        let field_count = parameter.typ.field_count() as usize;
+        if field_count != Self::N {
+            eyre::bail!(
+                "expected {} fields for {} but circuit has {field_count}",
+                Self::N,
+                Self::PARAMETER_NAME
+            );
+        }
+        Ok(())
+    }
+
+    /// Adds the game state as the `state` parameter to the circuit inputs.
+    pub(crate) fn add_to_inputs(
+        &self,
+        input_share: &mut BTreeMap<String, Rep3AcvmType<ark_bn254::Fr>>,
+    ) {
+        for (i, share) in self.as_field_array().into_iter().enumerate() {
+            input_share.insert(
+                format!("{}[{i}]", Self::PARAMETER_NAME),
+                Rep3AcvmType::Shared(share),
+            );
+        }
+    }
+}
+
+impl TryFrom<Vec<ArithmeticShare>> for SharedGameState {
+    type Error = eyre::Report;
+    fn try_from(value: Vec<ArithmeticShare>) -> eyre::Result<Self> {
+        let len = value.len();
+        let fields = <[ArithmeticShare; Self::N]>::try_from(value).map_err(|_| {
+            eyre::eyre!("expected {} fields for a game state but got {len}", Self::N)
+        })?;
+        Ok(Self::from_field_array(fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use co_noir::{Rep3AcvmType, Utils};
+    use mpc_core::protocols::rep3::Rep3PrimeFieldShare;
+    use noirc_abi::{AbiParameter, AbiType, AbiVisibility};
+    use noirc_artifacts::program::ProgramArtifact;
+
+    use super::SharedGameState;
+    use crate::mpc::ArithmeticShare;
+
+    fn share(i: u64) -> ArithmeticShare {
+        Rep3PrimeFieldShare::new(ark_bn254::Fr::from(i), ark_bn254::Fr::from(100 + i))
+    }
+
+    fn game_state_fields() -> Vec<ArithmeticShare> {
+        (0..SharedGameState::N as u64).map(share).collect()
+    }
+
+    fn commit_circuit() -> ProgramArtifact {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("data/circuits/taceo_commit.json");
+        Utils::get_program_artifact_from_file(&path).unwrap()
+    }
+
+    /// The commit circuit with a `state` parameter of the given prng state
+    /// length.
+    fn circuit_with_state(prng_len: u32) -> ProgramArtifact {
+        let mut circuit = commit_circuit();
+        let prng = AbiType::Struct {
+            path: "monty_hall::prng::Prng".to_owned(),
+            fields: vec![
+                (
+                    "state".to_owned(),
+                    AbiType::Array {
+                        length: prng_len,
+                        typ: Box::new(AbiType::Field),
+                    },
+                ),
+                ("position".to_owned(), AbiType::Field),
+            ],
+        };
+        circuit.abi.parameters.push(AbiParameter {
+            name: SharedGameState::PARAMETER_NAME.to_owned(),
+            typ: AbiType::Struct {
+                path: "monty_hall::game_state::GameState".to_owned(),
+                fields: vec![
+                    ("state".to_owned(), AbiType::Field),
+                    ("prng".to_owned(), prng),
+                    ("winning_door".to_owned(), AbiType::Field),
+                    ("chosen_door".to_owned(), AbiType::Field),
+                    ("open_door".to_owned(), AbiType::Field),
+                ],
+            },
+            visibility: AbiVisibility::Private,
+        });
+        circuit
+    }
+
+    #[test]
+    fn check_abi_of_game_state() {
+        SharedGameState::check_abi(&circuit_with_state(4)).unwrap();
+    }
+
+    #[test]
+    fn reject_circuit_without_state() {
+        assert!(SharedGameState::check_abi(&commit_circuit()).is_err());
+    }
+
+    #[test]
+    fn reject_state_of_other_layout() {
+        assert!(SharedGameState::check_abi(&circuit_with_state(3)).is_err());
+        assert!(SharedGameState::check_abi(&circuit_with_state(5)).is_err());
+    }
+
+    #[test]
+    fn add_to_inputs_in_field_order() {
+        let game_state = SharedGameState::try_from(game_state_fields()).unwrap();
+        let mut inputs = BTreeMap::new();
+        game_state.add_to_inputs(&mut inputs);
+        assert_eq!(inputs.len(), SharedGameState::N);
+        for (i, expected) in game_state_fields().into_iter().enumerate() {
+            let Some(Rep3AcvmType::Shared(input)) = inputs.get(&format!("state[{i}]")) else {
+                panic!("state[{i}] is not a shared input");
+            };
+            assert_eq!(*input, expected);
+        }
+    }
+
+    #[test]
+    fn field_array_round_trip() {
+        let game_state = SharedGameState::try_from(game_state_fields()).unwrap();
+        assert_eq!(game_state.prng_state[0], share(1));
+        assert_eq!(game_state.open_door, share(8));
+        assert_eq!(game_state.as_field_array().to_vec(), game_state_fields());
+    }
+
+    #[test]
+    fn reject_wrong_number_of_fields() {
+        let mut fields = game_state_fields();
+        fields.pop();
+        assert!(SharedGameState::try_from(fields).is_err());
+        let mut fields = game_state_fields();
+        fields.push(share(9));
+        assert!(SharedGameState::try_from(fields).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+use std::{collections::HashSet, path::Path};
+
+use ark_ff::{BigInteger as _, PrimeField as _, UniformRand as _};
+use co_noir::Rep3MpcNet;
+use crypto_box::aead::OsRng;
+use eyre::Context as _;
+use mpc_core::protocols::rep3::Rep3PrimeFieldShare;
+use mpc_core::protocols::rep3::id::PartyID;
+use mpc_core::protocols::rep3::network::Rep3Network;
+use uuid::Uuid;
+
+use crate::config::{NodeConfig, read_network_config};
+use crate::data_store::{DbStore, ResponseRow, StoredRow};
+use crate::mpc::ArithmeticShare;
+
+/// Hands the shares of the replaced party over to its replacement, which
+/// takes its id in the new network config. All three parties of the new
+/// network config run it, the replacement with an empty database, and write
+/// the new network config once they are done.
+///
+/// With `n` the next and `p` the previous party of the replaced party `k`,
+/// a secret is shared as `x = x_n + x_k + x_p`, where `n` holds
+/// `(x_n, x_k)` and `p` holds `(x_p, x_n)`. `n` samples `rho` and `tau`,
+/// sends them to `p`, and the new sharing is
+///
+/// `x_n' = x_n + rho`, `x_k' = x_k - rho + tau`, `x_p' = x_p - tau`.
+///
+/// The replacement receives `x_k'` from `n` and `x_p'` from `p`. Without
+/// `rho` and `tau`, the shares of the replaced party are useless against
+/// the new sharing.
+///
+/// The replacement also takes over the stored responses `n` and `p` both
+/// hold, such that it answers repeated requests like they do. Requests
+/// sent to every node alike get the same response on every node, the
+/// others (e.g. a sealed door for the replaced party) cannot be answered by
+/// the replacement anyway.
+pub(crate) async fn replace_party(
+    config: &NodeConfig,
+    db_store: DbStore,
+    replaced: usize,
+) -> eyre::Result<()> {
+    let new_network_config = config
+        .new_network_config
+        .as_deref()
+        .ok_or_else(|| eyre::eyre!("the hand-off needs the new network config"))?;
+    let network_config =
+        read_network_config(new_network_config).context("while reading new network config")?;
+    let replaced =
+        PartyID::try_from(replaced).map_err(|_| eyre::eyre!("invalid party id {replaced}"))?;
+    let is_replacement = network_config.my_id == replaced as usize;
+    db_store.check_no_running_jobs().await?;
+    let rows = db_store.load_rows().await?;
+    let responses = db_store.load_responses().await?;
+    if is_replacement && !(rows.is_empty() && responses.is_empty()) {
+        eyre::bail!("the replacement must start with an empty database");
+    }
+    let (net, rows, responses) = tokio::task::spawn_blocking(move || {
+        tracing::info!("establishing network...");
+        let net = Rep3MpcNet::new(network_config)?;
+        hand_off(net, replaced, rows, responses)
+    })
+    .await??;
+    if is_replacement {
+        db_store.insert_rows(&rows, &responses).await?;
+        // the remaining parties only keep the new sharing once ours is stored
+        tokio::task::spawn_blocking(move || take_over(net)).await??;
+    } else {
+        db_store.store_shares(&rows).await?;
+    }
+    tracing::info!(
+        "handed off {} rows and {} responses of party {replaced:?}",
+        rows.len(),
+        responses.len()
+    );
+    write_network_config(new_network_config, &config.network_config).with_context(|| {
+        format!(
+            "the shares are handed off, but the network config at {} is not replaced by {}",
+            config.network_config.display(),
+            new_network_config.display()
+        )
+    })?;
+    tracing::info!(
+        "wrote new network config to {}",
+        config.network_config.display()
+    );
+    Ok(())
+}
+
+/// Replaces the network config at `path` with the one at `new`, such that
+/// the node serves with the new network config from its next start.
+fn write_network_config(new: &Path, path: &Path) -> eyre::Result<()> {
+    let toml = std::fs::read(new)?;
+    // a crash leaves either the old or the new network config
+    let tmp = path.with_extension("toml.tmp");
+    std::fs::write(&tmp, toml)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn hand_off(
+    mut net: Rep3MpcNet,
+    replaced: PartyID,
+    mut rows: Vec<StoredRow>,
+    responses: Vec<ResponseRow>,
+) -> eyre::Result<(Rep3MpcNet, Vec<StoredRow>, Vec<ResponseRow>)> {
+    let next = replaced.next_id();
+    let prev = replaced.prev_id();
+    let me = net.get_id();
+    let layout = rows.iter().flat_map(StoredRow::layout).collect::<Vec<_>>();
+    if me == next {
+        // the remaining parties must hand off the same shares
+        net.send_many(prev, &layout)?;
+        if net.recv_many::<ark_bn254::Fr>(prev)? != layout {
+            eyre::bail!("party {prev:?} stores other shares than we do");
+        }
+        let public = rows
+            .iter()
+            .flat_map(|row| row.public.iter().cloned())
+            .collect::<Vec<_>>();
+        net.send_many(replaced, &layout)?;
+        net.send_many(replaced, &public)?;
+        net.send_many(replaced, &encode_responses(&responses))?;
+
+        let mut shares = rows
+            .iter_mut()
+            .flat_map(StoredRow::shares_mut)
+            .collect::<Vec<_>>();
+        let rho = (0..shares.len())
+            .map(|_| ark_bn254::Fr::rand(&mut OsRng))
+            .collect::<Vec<_>>();
+        let tau = (0..shares.len())
+            .map(|_| ark_bn254::Fr::rand(&mut OsRng))
+            .collect::<Vec<_>>();
+        net.send_many(prev, &rho)?;
+        net.send_many(prev, &tau)?;
+        let x_k = shares
+            .iter()
+            .zip(&rho)
+            .zip(&tau)
+            .map(|((share, rho), tau)| share.b - rho + tau)
+            .collect::<Vec<_>>();
+        net.send_many(replaced, &x_k)?;
+        wait_for_replacement(&mut net, replaced)?;
+        for ((share, rho), x_k) in shares.iter_mut().zip(rho).zip(x_k) {
+            **share = Rep3PrimeFieldShare::new(share.a + rho, x_k);
+        }
+        Ok((net, rows, Vec::new()))
+    } else if me == prev {
+        let next_layout = net.recv_many::<ark_bn254::Fr>(next)?;
+        net.send_many(next, &layout)?;
+        if next_layout != layout {
+            eyre::bail!("party {next:?} stores other shares than we do");
+        }
+        let mut shares = rows
+            .iter_mut()
+            .flat_map(StoredRow::shares_mut)
+            .collect::<Vec<_>>();
+        let rho = net.recv_many::<ark_bn254::Fr>(next)?;
+        let tau = net.recv_many::<ark_bn254::Fr>(next)?;
+        if rho.len() != shares.len() || tau.len() != shares.len() {
+            eyre::bail!("party {next:?} sent randomness for other shares");
+        }
+        let x_p = shares
+            .iter()
+            .zip(&tau)
+            .map(|(share, tau)| share.a - tau)
+            .collect::<Vec<_>>();
+        net.send_many(replaced, &encode_responses(&responses))?;
+        net.send_many(replaced, &x_p)?;
+        wait_for_replacement(&mut net, replaced)?;
+        for ((share, rho), x_p) in shares.iter_mut().zip(rho).zip(x_p) {
+            **share = Rep3PrimeFieldShare::new(x_p, share.b + rho);
+        }
+        Ok((net, rows, Vec::new()))
+    } else {
+        let layout = net.recv_many::<ark_bn254::Fr>(next)?;
+        let public = net.recv_many::<Vec<u8>>(next)?;
+        let next_responses = decode_responses(net.recv_many::<Vec<u8>>(next)?)?;
+        let x_k = net.recv_many::<ark_bn254::Fr>(next)?;
+        let prev_responses = decode_responses(net.recv_many::<Vec<u8>>(prev)?)?
+            .into_iter()
+            .collect::<HashSet<_>>();
+        let x_p = net.recv_many::<ark_bn254::Fr>(prev)?;
+        if x_k.len() != x_p.len() {
+            eyre::bail!("got {} and {} shares", x_k.len(), x_p.len());
+        }
+        let shares = x_k
+            .into_iter()
+            .zip(x_p)
+            .map(|(x_k, x_p)| Rep3PrimeFieldShare::new(x_k, x_p));
+        let rows = rows_from_layout(&layout, public, shares)?;
+        let num_responses = next_responses.len();
+        let responses = next_responses
+            .into_iter()
+            .filter(|response| prev_responses.contains(response))
+            .collect::<Vec<_>>();
+        if responses.len() < num_responses {
+            tracing::info!(
+                "skipping {} responses the other parties store differently",
+                num_responses - responses.len()
+            );
+        }
+        Ok((net, rows, responses))
+    }
+}
+
+/// Tells the remaining parties that the replacement stored its shares.
+fn take_over(mut net: Rep3MpcNet) -> eyre::Result<()> {
+    let me = net.get_id();
+    net.send_many(me.next_id(), &[ark_bn254::Fr::from(1u64)])?;
+    net.send_many(me.prev_id(), &[ark_bn254::Fr::from(1u64)])?;
+    Ok(())
+}
+
+fn wait_for_replacement(net: &mut Rep3MpcNet, replaced: PartyID) -> eyre::Result<()> {
+    net.recv_many::<ark_bn254::Fr>(replaced)
+        .with_context(|| format!("while waiting for party {replaced:?} to take over"))?;
+    Ok(())
+}
+
+/// The number of values [`encode_responses`] sends per response.
+const RESPONSE_VALUES: usize = 6;
+
+fn encode_responses(responses: &[ResponseRow]) -> Vec<Vec<u8>> {
+    responses
+        .iter()
+        .flat_map(|response| {
+            [
+                response.method.as_bytes().to_vec(),
+                response.request_id.as_bytes().to_vec(),
+                response.request_hash.clone(),
+                response
+                    .game_id
+                    .map(|game_id| game_id.as_bytes().to_vec())
+                    .unwrap_or_default(),
+                response.phase.clone().unwrap_or_default().into_bytes(),
+                response.response.clone(),
+            ]
+        })
+        .collect()
+}
+
+fn decode_responses(values: Vec<Vec<u8>>) -> eyre::Result<Vec<ResponseRow>> {
+    if values.len() % RESPONSE_VALUES != 0 {
+        eyre::bail!("got {} values, which are no responses", values.len());
+    }
+    let mut values = values.into_iter();
+    let mut responses = Vec::with_capacity(values.len() / RESPONSE_VALUES);
+    while let Some(method) = values.next() {
+        let mut next = || values.next().expect("checked the number of values");
+        let (request_id, request_hash, game_id, phase, response) =
+            (next(), next(), next(), next(), next());
+        responses.push(ResponseRow {
+            method: String::from_utf8(method)?,
+            request_id: String::from_utf8(request_id)?,
+            request_hash,
+            game_id: (!game_id.is_empty())
+                .then(|| Uuid::from_slice(&game_id))
+                .transpose()?,
+            phase: (!phase.is_empty())
+                .then(|| String::from_utf8(phase))
+                .transpose()?,
+            response,
+        });
+    }
+    Ok(responses)
+}
+
+/// Rebuilds the rows from their [`StoredRow::layout`], their public values
+/// and the new shares.
+fn rows_from_layout(
+    layout: &[ark_bn254::Fr],
+    public: Vec<Vec<u8>>,
+    mut shares: impl Iterator<Item = ArithmeticShare>,
+) -> eyre::Result<Vec<StoredRow>> {
+    let to_u64 = |field: &ark_bn254::Fr| field.into_bigint().0[0];
+    let mut layout = layout.iter();
+    let mut public = public.into_iter();
+    let mut rows = Vec::new();
+    while let Some(table) = layout.next() {
+        let table = to_u64(table) as usize;
+        let game_id = layout
+            .next()
+            .ok_or_else(|| eyre::eyre!("layout ends without a game id"))?;
+        let game_id = Uuid::from_u128(u128::from_le_bytes(
+            game_id.into_bigint().to_bytes_le()[..16]
+                .try_into()
+                .expect("field has 32 bytes"),
+        ));
+        let (num_public, num_shares) = StoredRow::num_columns(table)?;
+        let row_public = public.by_ref().take(num_public).collect::<Vec<_>>();
+        if row_public.len() != num_public {
+            eyre::bail!("missing public values of game {game_id}");
+        }
+        let mut row_shares = Vec::with_capacity(num_shares);
+        for _ in 0..num_shares {
+            let count = layout
+                .next()
+                .ok_or_else(|| eyre::eyre!("layout ends without a share count"))?;
+            let column = shares
+                .by_ref()
+                .take(to_u64(count) as usize)
+                .collect::<Vec<_>>();
+            if column.len() as u64 != to_u64(count) {
+                eyre::bail!("missing shares of game {game_id}");
+            }
+            row_shares.push(column);
+        }
+        rows.push(StoredRow {
+            table,
+            game_id,
+            public: row_public,
+            shares: row_shares,
+        });
+    }
+    if public.next().is_some() || shares.next().is_some() {
+        eyre::bail!("got more values than the layout holds");
+    }
+    Ok(rows)
+}
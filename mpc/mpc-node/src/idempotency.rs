@@ -0,0 +1,72 @@
+use prost::Message;
+use protos::monty_hall::{
+    FinishGameRequest, GenerateNullifyingKeyRequest, InitGameRequest, RefreshSharesRequest,
+    RevealDoorRequest, RollbackJobRequest, SampleRandRequest,
+};
+use sha2::{Digest as _, Sha256};
+
+use crate::data_store::ReservedResponse;
+
+/// A request changing the state of the node. The node stores its response
+/// under the request id chosen by the caller and answers a repeated request
+/// with it.
+pub(crate) trait IdempotentRequest: Message + Clone {
+    /// The RPC of the request, request ids are unique per RPC.
+    const METHOD: &'static str;
+
+    fn request_id(&self) -> &str;
+
+    fn clear_request_id(&mut self);
+
+    /// The hash of the request without its id. A request id must not be
+    /// reused for a request with other arguments.
+    fn request_hash(&self) -> Vec<u8> {
+        let mut request = self.clone();
+        request.clear_request_id();
+        Sha256::digest(request.encode_to_vec()).to_vec()
+    }
+}
+
+/// A response stored for a request id.
+pub(crate) struct StoredResponse {
+    pub(crate) request_hash: Vec<u8>,
+    pub(crate) response: Vec<u8>,
+}
+
+/// What a request finds stored for its request id.
+pub(crate) enum Reservation {
+    /// The request id is reserved for the request, which has to run now.
+    Reserved(ReservedResponse),
+    /// A request with the id and the given hash runs right now.
+    Pending { request_hash: Vec<u8> },
+    /// A request with the id ran already.
+    Done(StoredResponse),
+}
+
+macro_rules! idempotent_requests {
+    ($($request:ty => $method:literal,)*) => {
+        $(
+            impl IdempotentRequest for $request {
+                const METHOD: &'static str = $method;
+
+                fn request_id(&self) -> &str {
+                    &self.request_id
+                }
+
+                fn clear_request_id(&mut self) {
+                    self.request_id.clear();
+                }
+            }
+        )*
+    };
+}
+
+idempotent_requests! {
+    SampleRandRequest => "sample_rand",
+    GenerateNullifyingKeyRequest => "generate_nullifying_key",
+    InitGameRequest => "init_game",
+    RevealDoorRequest => "reveal_door",
+    FinishGameRequest => "finish_game",
+    RefreshSharesRequest => "refresh_shares",
+    RollbackJobRequest => "rollback_job",
+}
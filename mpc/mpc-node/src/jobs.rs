@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::phase::GamePhase;
+
+/// The status of a game step in the write-ahead job log `mpc_job`. A job
+/// is keyed by its game and the phase the step moves the game to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum JobStatus {
+    /// The MPC of the step runs, nothing of it is stored yet.
+    Running,
+    /// The result of the step is stored.
+    Completed,
+    /// The step failed, was interrupted by a crash, or was undone.
+    RolledBack,
+}
+
+/// A job of the log, see [`JobStatus`].
+pub(crate) struct Job {
+    pub(crate) game_id: Uuid,
+    pub(crate) phase: GamePhase,
+    pub(crate) status: JobStatus,
+    pub(crate) output: Vec<u8>,
+    /// The time since the job changed its status.
+    pub(crate) age: Duration,
+}
+
+/// A step that runs already or is completed was requested again.
+#[derive(Debug, thiserror::Error)]
+#[error("{phase} of game {game_id} is running or completed already")]
+pub(crate) struct DuplicateJob {
+    pub(crate) game_id: Uuid,
+    pub(crate) phase: GamePhase,
+}
+
+/// A step that cannot be undone, e.g. because a later step is stored.
+#[derive(Debug, thiserror::Error)]
+#[error("cannot roll back {phase} of game {game_id}: {reason}")]
+pub(crate) struct CannotRollBack {
+    pub(crate) game_id: Uuid,
+    pub(crate) phase: GamePhase,
+    pub(crate) reason: &'static str,
+}
+
+/// The shares of all games cannot be replaced while a step runs, the step
+/// would store shares of the old sharing.
+#[derive(Debug, thiserror::Error)]
+#[error("{running} jobs are running, the shares cannot be replaced")]
+pub(crate) struct JobsRunning {
+    pub(crate) running: i64,
+}
+
+impl JobStatus {
+    pub(crate) fn tag(self) -> &'static str {
+        match self {
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::RolledBack => "rolled_back",
+        }
+    }
+
+    pub(crate) fn from_tag(tag: &str) -> eyre::Result<Self> {
+        match tag {
+            "running" => Ok(JobStatus::Running),
+            "completed" => Ok(JobStatus::Completed),
+            "rolled_back" => Ok(JobStatus::RolledBack),
+            _ => eyre::bail!("unknown job status {tag}"),
+        }
+    }
+}
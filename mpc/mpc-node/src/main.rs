@@ -11,7 +11,17 @@ use tonic::transport::Server;
 mod config;
 mod crypto_device;
 mod data_store;
+mod error;
+mod game_state;
+mod handoff;
+mod idempotency;
+mod jobs;
 mod mpc;
+mod network;
+mod phase;
+mod refresh;
+mod stages;
+mod witness;
 
 fn install_tracing() {
     use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
@@ -27,10 +37,14 @@ fn install_tracing() {
 async fn main() -> eyre::Result<()> {
     install_tracing();
     let config = Arc::new(NodeConfig::parse());
+    config.check_parties()?;
     let crypto_device = CryptoDevice::init(&config);
     let db_store = DbStore::init(&config).await?;
+    if let Some(replaced) = config.replace_party {
+        return handoff::replace_party(&config, db_store, replaced).await;
+    }
     tracing::info!("serving node on {}", config.bind_addr);
-    let mpc_node = MpcNode::init(Arc::clone(&config), db_store)?;
+    let mpc_node = MpcNode::init(Arc::clone(&config), db_store, crypto_device)?;
     let service = MpcNodeServiceServer::new(mpc_node);
     Server::builder()
         .add_service(service)
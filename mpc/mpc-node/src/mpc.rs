@@ -1,66 +1,161 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use ark_ff::One as _;
+use ark_ff::Zero as _;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use co_builder::prelude::{ProverCrs, ZeroKnowledge};
 use co_noir::{
-    Bn254, CrsParser, Poseidon2Sponge, Rep3AcvmType, Rep3CoUltraHonk, Rep3MpcNet, Utils,
+    Bn254, CrsParser, Poseidon2Sponge, Rep3AcvmType, Rep3CoUltraHonk, Rep3MpcNet, UltraHonk, Utils,
+    VerifyingKey,
 };
+use eyre::Context as _;
 use mpc_core::protocols::rep3::Rep3PrimeFieldShare;
 use mpc_core::protocols::rep3::network::{IoContext, Rep3Network};
 use noirc_artifacts::program::ProgramArtifact;
+use prost::Message;
 use protos::monty_hall::mpc_node_service_server::MpcNodeService;
 use protos::monty_hall::{
-    InitGameRequest, InitGameResponse, RevealDoorRequest, RevealDoorResponse, SampleRandRequest,
-    SampleRandResponse,
+    FinishGameRequest, FinishGameResponse, GenerateNullifyingKeyRequest,
+    GenerateNullifyingKeyResponse, GetPublicKeyRequest, GetPublicKeyResponse, InitGameRequest,
+    InitGameResponse, Job, ListJobsRequest, ListJobsResponse, RefreshSharesRequest,
+    RefreshSharesResponse, RevealDoorRequest, RevealDoorResponse, RollbackJobRequest,
+    RollbackJobResponse, SampleRandRequest, SampleRandResponse, Stage, WatchJobRequest,
 };
+use protos::monty_hall::{GamePhase as ProtoGamePhase, JobStatus as ProtoJobStatus};
+use tokio::sync::RwLock;
 use tonic::async_trait;
 use ultrahonk::prelude::HonkProof;
+use uuid::Uuid;
 
 use crate::config::NodeConfig;
+use crate::crypto_device::CryptoDevice;
 use crate::data_store::DbStore;
+use crate::error::{NodeError, NodeResult};
+use crate::game_state::SharedGameState;
+use crate::idempotency::{IdempotentRequest, Reservation};
+use crate::jobs::JobStatus;
+use crate::network::{NetworkSessions, SessionId, Step};
+use crate::phase::GamePhase;
+use crate::refresh;
+use crate::stages::{StageEvents, StageReporter, StageWatch};
+use crate::witness::WitnessLayout;
 
 const CRS_SIZE: usize = 4096;
+/// The nodes prove without zero knowledge, verifiers have to match.
+const PROOF_ZK: ZeroKnowledge = ZeroKnowledge::No;
+/// How often a repeated request checks whether the first one is done.
+const RESPONSE_POLL_INTERVAL: Duration = Duration::from_millis(250);
 
 type AcvmType = Rep3AcvmType<ark_bn254::Fr>;
 pub type ArithmeticShare = Rep3PrimeFieldShare<ark_bn254::Fr>;
 
 pub struct MpcNode {
-    config: Arc<NodeConfig>,
     crs: Arc<ProverCrs<Bn254>>,
     db_store: DbStore,
+    crypto_device: CryptoDevice,
+    sessions: NetworkSessions,
+    /// Game steps hold it for reading, refreshing the shares of all games
+    /// holds it for writing, so no step works on shares being refreshed.
+    refresh_lock: RwLock<()>,
+    stages: StageEvents,
     commit_circuit: ProgramArtifact,
+    nullifying_key_circuit: ProgramArtifact,
     init_circuit: ProgramArtifact,
+    choose_circuit: ProgramArtifact,
+    switch_circuit: ProgramArtifact,
+    init_vk: Arc<VerifyingKey<Bn254>>,
+    choose_vk: Arc<VerifyingKey<Bn254>>,
+    switch_vk: Arc<VerifyingKey<Bn254>>,
 }
 
 impl MpcNode {
-    pub(crate) fn init(config: Arc<NodeConfig>, db_store: DbStore) -> eyre::Result<Self> {
+    pub(crate) fn init(
+        config: Arc<NodeConfig>,
+        db_store: DbStore,
+        crypto_device: CryptoDevice,
+    ) -> eyre::Result<Self> {
         tracing::info!("Reading crs from {}", config.crs_path.display());
-        let crs = CrsParser::<Bn254>::get_crs_g1(&config.crs_path, CRS_SIZE, ZeroKnowledge::Yes)?;
+        let crs = Arc::new(CrsParser::<Bn254>::get_crs_g1(
+            &config.crs_path,
+            CRS_SIZE,
+            ZeroKnowledge::Yes,
+        )?);
+        tracing::info!(
+            "Reading verifier crs from {}",
+            config.verifier_crs_path.display()
+        );
+        let verifier_crs = CrsParser::<Bn254>::get_crs_g2(&config.verifier_crs_path)?;
         tracing::info!(
             "reading commit circuit from {}...",
             config.commit_circuit.display()
         );
         let commit_circuit = Utils::get_program_artifact_from_file(&config.commit_circuit)?;
+        tracing::info!(
+            "reading nullifying key circuit from {}...",
+            config.nullifying_key_circuit.display()
+        );
+        let nullifying_key_circuit =
+            Utils::get_program_artifact_from_file(&config.nullifying_key_circuit)?;
         tracing::info!(
             "reading init circuit from {}...",
             config.init_circuit.display()
         );
         let init_circuit = Utils::get_program_artifact_from_file(&config.init_circuit)?;
+        tracing::info!(
+            "reading choose circuit from {}...",
+            config.choose_circuit.display()
+        );
+        let choose_circuit = Utils::get_program_artifact_from_file(&config.choose_circuit)?;
+        SharedGameState::check_abi(&choose_circuit).context("while reading choose circuit")?;
+        tracing::info!(
+            "reading switch circuit from {}...",
+            config.switch_circuit.display()
+        );
+        let switch_circuit = Utils::get_program_artifact_from_file(&config.switch_circuit)?;
+        SharedGameState::check_abi(&switch_circuit).context("while reading switch circuit")?;
+        tracing::info!("computing verifying keys...");
+        let init_vk = Self::verifying_key(&init_circuit, &crs, verifier_crs)?;
+        let choose_vk = Self::verifying_key(&choose_circuit, &crs, verifier_crs)?;
+        let switch_vk = Self::verifying_key(&switch_circuit, &crs, verifier_crs)?;
 
         Ok(Self {
-            config,
+            sessions: NetworkSessions::new(config)?,
+            refresh_lock: RwLock::default(),
+            stages: StageEvents::default(),
             db_store,
-            crs: Arc::new(crs),
+            crypto_device,
+            crs,
             commit_circuit,
+            nullifying_key_circuit,
             init_circuit,
+            choose_circuit,
+            switch_circuit,
+            init_vk,
+            choose_vk,
+            switch_vk,
         })
     }
+
+    fn verifying_key(
+        circuit: &ProgramArtifact,
+        crs: &Arc<ProverCrs<Bn254>>,
+        verifier_crs: ark_bn254::G2Affine,
+    ) -> eyre::Result<Arc<VerifyingKey<Bn254>>> {
+        let constraint_system = Utils::get_constraint_system_from_artifact(circuit, true);
+        let vk = co_noir::generate_vk::<Bn254>(
+            &constraint_system,
+            Arc::clone(crs),
+            verifier_crs,
+            false,
+        )?;
+        Ok(Arc::new(vk))
+    }
 }
 
 pub(crate) struct InitState {
     pub(crate) proof: HonkProof<ark_bn254::Fr>,
+    pub(crate) game_state: SharedGameState,
     pub(crate) game_state_r: ArithmeticShare,
     pub(crate) game_state_c: ark_bn254::Fr,
 }
@@ -71,10 +166,79 @@ pub(crate) struct RootRandomness {
     pub(crate) seed_c: ark_bn254::Fr,
 }
 
+/// The current game state of a running game, as stored by this party.
+#[derive(Clone)]
+pub(crate) struct GameState {
+    pub(crate) game_state: SharedGameState,
+    pub(crate) game_state_r: ArithmeticShare,
+    pub(crate) game_state_c: ark_bn254::Fr,
+}
+
+/// The shared nullifying key of the network together with its public key
+/// `(x, y, is_infinite)` on the embedded curve.
+pub(crate) struct NullifyingKey {
+    pub(crate) sk: ArithmeticShare,
+    pub(crate) pk: (ark_bn254::Fr, ark_bn254::Fr, bool),
+}
+
+/// Our share of the committed door choice of the player.
+pub(crate) struct DoorChoice {
+    pub(crate) door: ArithmeticShare,
+    pub(crate) door_r: [ArithmeticShare; 2],
+    pub(crate) door_c: ark_bn254::Fr,
+}
+
+/// Our share of the committed switch decision of the player.
+pub(crate) struct SwitchChoice {
+    pub(crate) switch: ArithmeticShare,
+    pub(crate) switch_r: [ArithmeticShare; 2],
+    pub(crate) switch_c: ark_bn254::Fr,
+}
+
+pub(crate) struct RevealDoorState {
+    pub(crate) proof: HonkProof<ark_bn254::Fr>,
+    pub(crate) game_state: GameState,
+    pub(crate) opened_door: ArithmeticShare,
+    pub(crate) opened_door_r: ArithmeticShare,
+    pub(crate) opened_door_c: ark_bn254::Fr,
+    pub(crate) game_state_nullifier: ark_bn254::Fr,
+}
+
+pub(crate) struct FinishGameState {
+    pub(crate) proof: HonkProof<ark_bn254::Fr>,
+    pub(crate) win: ArithmeticShare,
+    pub(crate) win_r: ArithmeticShare,
+    pub(crate) win_c: ark_bn254::Fr,
+    pub(crate) game_state_nullifier: ark_bn254::Fr,
+}
+
+impl DoorChoice {
+    fn decrypt(request: &RevealDoorRequest, crypto_device: &CryptoDevice) -> eyre::Result<Self> {
+        let (door, door_r) = crypto_device.decrypt_player_input(&request.door_ciphertext)?;
+        Ok(Self {
+            door,
+            door_r,
+            door_c: ark_bn254::Fr::deserialize_compressed(request.door_c.as_slice())?,
+        })
+    }
+}
+
+impl SwitchChoice {
+    fn decrypt(request: &FinishGameRequest, crypto_device: &CryptoDevice) -> eyre::Result<Self> {
+        let (switch, switch_r) = crypto_device.decrypt_player_input(&request.switch_ciphertext)?;
+        Ok(Self {
+            switch,
+            switch_r,
+            switch_c: ark_bn254::Fr::deserialize_compressed(request.switch_c.as_slice())?,
+        })
+    }
+}
+
 impl MpcNode {
     fn sample_root_rand(
         network: Rep3MpcNet,
         commit_circuit: ProgramArtifact,
+        stages: StageReporter,
     ) -> eyre::Result<RootRandomness> {
         tracing::info!("creating io context");
         let mut io_context = IoContext::init(network)?;
@@ -85,7 +249,7 @@ impl MpcNode {
         let seed = Rep3PrimeFieldShare::new(seed_a, seed_b);
         let seed_r = Rep3PrimeFieldShare::new(seed_r_a, seed_r_b);
         let network = io_context.network;
-        let (seed_c, _) = Self::commit(&seed, &seed_r, commit_circuit, network)?;
+        let (seed_c, _) = Self::commit(&seed, &seed_r, commit_circuit, network, &stages)?;
         Ok(RootRandomness {
             seed,
             seed_r,
@@ -93,11 +257,44 @@ impl MpcNode {
         })
     }
 
+    fn generate_nullifying_key(
+        network: Rep3MpcNet,
+        nullifying_key_circuit: ProgramArtifact,
+        stages: StageReporter,
+    ) -> eyre::Result<NullifyingKey> {
+        tracing::info!("creating io context");
+        let mut io_context = IoContext::init(network)?;
+
+        tracing::info!("squeezing elements");
+        let (sk_a, sk_b) = io_context.random_fes::<ark_bn254::Fr>();
+        let sk = Rep3PrimeFieldShare::new(sk_a, sk_b);
+        let network = io_context.network;
+
+        // the public key is an output of the circuit, so it is opened during
+        // witness extension
+        let mut input_share = BTreeMap::default();
+        input_share.insert(
+            "nullifying_key".to_string(),
+            Rep3AcvmType::Shared(sk.clone()),
+        );
+        let layout = WitnessLayout::new(&nullifying_key_circuit)?;
+        let time = Instant::now();
+        let (witness_share, _) =
+            co_noir::generate_witness_rep3(input_share, nullifying_key_circuit, network)?;
+        stages.report(Stage::WitnessExtension, time.elapsed());
+        let [x, y, is_infinite] = layout.public_outputs(&witness_share)?;
+        Ok(NullifyingKey {
+            sk,
+            pk: (x, y, !is_infinite.is_zero()),
+        })
+    }
+
     fn init_game(
         crs: Arc<ProverCrs<Bn254>>,
         network: Rep3MpcNet,
         root_randomness: RootRandomness,
         init_circuit: ProgramArtifact,
+        stages: StageReporter,
     ) -> eyre::Result<InitState> {
         tracing::info!("creating io context");
         let mut io_context = IoContext::init(network)?;
@@ -105,10 +302,9 @@ impl MpcNode {
         tracing::info!("squeezing elements");
         let (out_r_a, out_r_b) = io_context.random_fes::<ark_bn254::Fr>();
         let out_r = Rep3PrimeFieldShare::new(out_r_a, out_r_b);
+        let id = io_context.id;
         let network = io_context.network;
 
-        let constraint_system = Utils::get_constraint_system_from_artifact(&init_circuit, true);
-
         // generate the proof
         let mut input_share = BTreeMap::default();
         input_share.insert(
@@ -125,72 +321,259 @@ impl MpcNode {
         );
         input_share.insert("out_r".to_string(), Rep3AcvmType::Shared(out_r.clone()));
 
+        let layout = WitnessLayout::new(&init_circuit)?;
         let time = Instant::now();
         let (witness_share, net) =
-            co_noir::generate_witness_rep3(input_share, init_circuit, network)?;
+            co_noir::generate_witness_rep3(input_share, init_circuit.clone(), network)?;
         let elapsed_witness = time.elapsed();
+        tracing::info!(
+            "wit extension: {}.{} secs",
+            elapsed_witness.as_secs(),
+            elapsed_witness.subsec_nanos()
+        );
+        stages.report(Stage::WitnessExtension, elapsed_witness);
 
-        // hardcoded for the time being
-        let game_state_c = if let Rep3AcvmType::Public(commitment) = witness_share[4] {
-            commitment
-        } else {
-            eyre::bail!("THIS SHOULD NOT HAPPEN - COMMITMENT IS NOT ON 4 ANYMORE");
-        };
+        let [game_state_c] = layout.public_outputs(&witness_share)?;
+        let game_state = SharedGameState::try_from(layout.private_state(
+            &witness_share,
+            SharedGameState::PRIVATE_STATE_NAME,
+            id,
+        )?)?;
 
-        // generate proving key and vk
-        let (pk, net) =
-            co_noir::generate_proving_key_rep3(net, &constraint_system, witness_share, false)?;
+        let proof = Self::prove(&crs, &init_circuit, witness_share, net, &stages)?;
 
-        let elapsed_pk = time.elapsed();
+        let elapsed = time.elapsed();
+        tracing::info!("executed init circuit!");
+        tracing::info!(
+            "total time: {}.{} secs",
+            elapsed.as_secs(),
+            elapsed.subsec_nanos()
+        );
 
-        // generate proof
-        let (proof, _) =
-            Rep3CoUltraHonk::<_, _, Poseidon2Sponge>::prove(net, pk, &crs, ZeroKnowledge::No)?;
+        Ok(InitState {
+            proof,
+            game_state,
+            game_state_r: out_r,
+            game_state_c,
+        })
+    }
+
+    fn reveal_door(
+        crs: Arc<ProverCrs<Bn254>>,
+        network: Rep3MpcNet,
+        game_state: GameState,
+        nullifying_key: NullifyingKey,
+        door_choice: DoorChoice,
+        choose_circuit: ProgramArtifact,
+        stages: StageReporter,
+    ) -> eyre::Result<RevealDoorState> {
+        tracing::info!("creating io context");
+        let mut io_context = IoContext::init(network)?;
 
-        let elapsed_proof = time.elapsed();
+        tracing::info!("squeezing elements");
+        let (out1_r_a, out1_r_b) = io_context.random_fes::<ark_bn254::Fr>();
+        let (out2_r_a, out2_r_b) = io_context.random_fes::<ark_bn254::Fr>();
+        let out1_r = Rep3PrimeFieldShare::new(out1_r_a, out1_r_b);
+        let out2_r = Rep3PrimeFieldShare::new(out2_r_a, out2_r_b);
+        let id = io_context.id;
+        let network = io_context.network;
 
-        tracing::info!("executed init circuit!");
+        let mut input_share = Self::game_state_inputs(game_state, nullifying_key);
+        input_share.insert("door".to_string(), Rep3AcvmType::Shared(door_choice.door));
+        for (i, door_r) in door_choice.door_r.into_iter().enumerate() {
+            input_share.insert(format!("door_r[{i}]"), Rep3AcvmType::Shared(door_r));
+        }
+        input_share.insert(
+            "door_c".to_string(),
+            Rep3AcvmType::Public(door_choice.door_c),
+        );
+        input_share.insert("out1_r".to_string(), Rep3AcvmType::Shared(out1_r.clone()));
+        input_share.insert("out2_r".to_string(), Rep3AcvmType::Shared(out2_r.clone()));
+
+        let layout = WitnessLayout::new(&choose_circuit)?;
+        let time = Instant::now();
+        let (witness_share, net) =
+            co_noir::generate_witness_rep3(input_share, choose_circuit.clone(), network)?;
+        let elapsed_witness = time.elapsed();
+        tracing::info!(
+            "wit extension: {}.{} secs",
+            elapsed_witness.as_secs(),
+            elapsed_witness.subsec_nanos()
+        );
+        stages.report(Stage::WitnessExtension, elapsed_witness);
+
+        let [game_state_c, opened_door_c, game_state_nullifier] =
+            layout.public_outputs(&witness_share)?;
+        let new_game_state = SharedGameState::try_from(layout.private_state(
+            &witness_share,
+            SharedGameState::PRIVATE_STATE_NAME,
+            id,
+        )?)?;
+        let [opened_door] = layout
+            .private_state(&witness_share, "door", id)?
+            .try_into()
+            .map_err(|_| eyre::eyre!("opened door must be a single field element"))?;
+
+        let proof = Self::prove(&crs, &choose_circuit, witness_share, net, &stages)?;
+
+        let elapsed = time.elapsed();
+        tracing::info!("executed choose circuit!");
         tracing::info!(
             "total time: {}.{} secs",
-            elapsed_proof.as_secs(),
-            elapsed_proof.subsec_nanos()
+            elapsed.as_secs(),
+            elapsed.subsec_nanos()
         );
 
+        Ok(RevealDoorState {
+            proof,
+            game_state: GameState {
+                game_state: new_game_state,
+                game_state_r: out1_r,
+                game_state_c,
+            },
+            opened_door,
+            opened_door_r: out2_r,
+            opened_door_c,
+            game_state_nullifier,
+        })
+    }
+
+    fn finish_game(
+        crs: Arc<ProverCrs<Bn254>>,
+        network: Rep3MpcNet,
+        game_state: GameState,
+        nullifying_key: NullifyingKey,
+        switch_choice: SwitchChoice,
+        switch_circuit: ProgramArtifact,
+        stages: StageReporter,
+    ) -> eyre::Result<FinishGameState> {
+        tracing::info!("creating io context");
+        let mut io_context = IoContext::init(network)?;
+
+        tracing::info!("squeezing elements");
+        let (out_r_a, out_r_b) = io_context.random_fes::<ark_bn254::Fr>();
+        let out_r = Rep3PrimeFieldShare::new(out_r_a, out_r_b);
+        let id = io_context.id;
+        let network = io_context.network;
+
+        let mut input_share = Self::game_state_inputs(game_state, nullifying_key);
+        input_share.insert(
+            "switch".to_string(),
+            Rep3AcvmType::Shared(switch_choice.switch),
+        );
+        for (i, switch_r) in switch_choice.switch_r.into_iter().enumerate() {
+            input_share.insert(format!("switch_r[{i}]"), Rep3AcvmType::Shared(switch_r));
+        }
+        input_share.insert(
+            "switch_c".to_string(),
+            Rep3AcvmType::Public(switch_choice.switch_c),
+        );
+        input_share.insert("out_r".to_string(), Rep3AcvmType::Shared(out_r.clone()));
+
+        let layout = WitnessLayout::new(&switch_circuit)?;
+        let time = Instant::now();
+        let (witness_share, net) =
+            co_noir::generate_witness_rep3(input_share, switch_circuit.clone(), network)?;
+        let elapsed_witness = time.elapsed();
         tracing::info!(
             "wit extension: {}.{} secs",
             elapsed_witness.as_secs(),
             elapsed_witness.subsec_nanos()
         );
+        stages.report(Stage::WitnessExtension, elapsed_witness);
+
+        let [win_c, game_state_nullifier] = layout.public_outputs(&witness_share)?;
+        let [win] = layout
+            .private_state(&witness_share, "win", id)?
+            .try_into()
+            .map_err(|_| eyre::eyre!("win must be a single field element"))?;
+
+        let proof = Self::prove(&crs, &switch_circuit, witness_share, net, &stages)?;
+
+        let elapsed = time.elapsed();
+        tracing::info!("executed switch circuit!");
+        tracing::info!(
+            "total time: {}.{} secs",
+            elapsed.as_secs(),
+            elapsed.subsec_nanos()
+        );
+
+        Ok(FinishGameState {
+            proof,
+            win,
+            win_r: out_r,
+            win_c,
+            game_state_nullifier,
+        })
+    }
+
+    /// The inputs the choose and switch circuits share: the current game
+    /// state with its commitment and the nullifying key.
+    fn game_state_inputs(
+        game_state: GameState,
+        nullifying_key: NullifyingKey,
+    ) -> BTreeMap<String, AcvmType> {
+        let mut input_share = BTreeMap::default();
+        game_state.game_state.add_to_inputs(&mut input_share);
+        input_share.insert(
+            "state_r".to_string(),
+            Rep3AcvmType::Shared(game_state.game_state_r),
+        );
+        input_share.insert(
+            "state_c".to_string(),
+            Rep3AcvmType::Public(game_state.game_state_c),
+        );
+        input_share.insert(
+            "nullifying_key".to_string(),
+            Rep3AcvmType::Shared(nullifying_key.sk),
+        );
+        let (pk_x, pk_y, pk_is_infinite) = nullifying_key.pk;
+        input_share.insert(
+            "nullifying_pub_key[0]".to_string(),
+            Rep3AcvmType::Public(pk_x),
+        );
+        input_share.insert(
+            "nullifying_pub_key[1]".to_string(),
+            Rep3AcvmType::Public(pk_y),
+        );
+        input_share.insert(
+            "nullifying_pub_key[2]".to_string(),
+            Rep3AcvmType::Public(ark_bn254::Fr::from(pk_is_infinite)),
+        );
+        input_share
+    }
 
-        let elapsed_pk = elapsed_pk - elapsed_witness;
+    fn prove(
+        crs: &ProverCrs<Bn254>,
+        circuit: &ProgramArtifact,
+        witness_share: Vec<AcvmType>,
+        net: Rep3MpcNet,
+        stages: &StageReporter,
+    ) -> eyre::Result<HonkProof<ark_bn254::Fr>> {
+        let constraint_system = Utils::get_constraint_system_from_artifact(circuit, true);
+        let time = Instant::now();
+
+        // generate proving key and vk
+        let (pk, net) =
+            co_noir::generate_proving_key_rep3(net, &constraint_system, witness_share, false)?;
+        let elapsed_pk = time.elapsed();
         tracing::info!(
             "pk creation: {}.{} secs",
             elapsed_pk.as_secs(),
             elapsed_pk.subsec_nanos()
         );
+        stages.report(Stage::ProvingKey, elapsed_pk);
 
-        let elapsed_proof = elapsed_proof - elapsed_pk;
+        // generate proof
+        let (proof, _) = Rep3CoUltraHonk::<_, _, Poseidon2Sponge>::prove(net, pk, crs, PROOF_ZK)?;
+        let elapsed_proof = time.elapsed() - elapsed_pk;
         tracing::info!(
             "proof creation: {}.{} secs",
             elapsed_proof.as_secs(),
             elapsed_proof.subsec_nanos()
         );
-
-        Ok(InitState {
-            proof,
-            game_state_r: out_r,
-            game_state_c,
-        })
-    }
-
-    fn extract_game_state(witness: &[AcvmType]) -> Vec<()> {
-        if let AcvmType::Shared(state) = witness[11] {}
-        if let AcvmType::Shared(state) = witness[12] {}
-        if let AcvmType::Shared(state) = witness[13] {}
-        if let AcvmType::Shared(state) = witness[14] {}
-        if let AcvmType::Shared(state) = witness[15] {}
-
-        todo!()
+        stages.report(Stage::Proof, elapsed_proof);
+        Ok(proof)
     }
 
     fn commit(
@@ -198,13 +581,13 @@ impl MpcNode {
         rand: &ArithmeticShare,
         commit_circuit: ProgramArtifact,
         network: Rep3MpcNet,
+        stages: &StageReporter,
     ) -> eyre::Result<(ark_bn254::Fr, Rep3MpcNet)> {
         let mut input_share = BTreeMap::default();
         input_share.insert("x".to_string(), Rep3AcvmType::Shared(data.to_owned()));
         input_share.insert("meta".to_string(), Rep3AcvmType::Shared(rand.to_owned()));
+        let layout = WitnessLayout::new(&commit_circuit)?;
         let start = Instant::now();
-        // TODO we need a better way to simply execute a noir program and obtain
-        // the outputs
         let (result_witness_share, net) =
             co_noir::generate_witness_rep3(input_share, commit_circuit, network)?;
         let elapsed = start.elapsed();
@@ -213,106 +596,558 @@ impl MpcNode {
             elapsed.as_secs(),
             elapsed.subsec_nanos()
         );
-        if let Rep3AcvmType::Public(commitment) = result_witness_share[2] {
-            Ok((commitment, net))
-        } else {
-            eyre::bail!("THIS SHOULD NOT HAPPEN - COMMITMENT IS NOT ON 2 ANYMORE");
-        }
+        stages.report(Stage::WitnessExtension, elapsed);
+        let [commitment] = layout.public_outputs(&result_witness_share)?;
+        Ok((commitment, net))
     }
 }
 
-#[async_trait]
-impl MpcNodeService for MpcNode {
-    async fn sample_rand(
-        &self,
-        _: tonic::Request<SampleRandRequest>,
-    ) -> Result<tonic::Response<SampleRandResponse>, tonic::Status> {
-        let network_config = self.config.network_config().unwrap();
-        let commit_circuit = self.commit_circuit.clone();
-        tracing::info!("Started to sample root randomness!");
-        // we need to sample some randomness and commit to it in MPC
-        // The network can't run in tokio runtime because it creates a
-        // runtime internally. Therefore we need to do this
-        // roundtrip to sync land and back
+fn parse_game_id(game_id: &str) -> NodeResult<Uuid> {
+    Uuid::parse_str(game_id)
+        .map_err(|_| NodeError::InvalidArgument(format!("invalid game id: {game_id}")))
+}
+
+/// The attempt id the orchestrator sent along with a request running an MPC
+/// session, see [`protos::SESSION_ATTEMPT_KEY`].
+fn session_attempt<T>(request: &tonic::Request<T>) -> NodeResult<Uuid> {
+    let attempt = request
+        .metadata()
+        .get(protos::SESSION_ATTEMPT_KEY)
+        .ok_or_else(|| NodeError::InvalidArgument("request without attempt id".to_owned()))?;
+    attempt
+        .to_str()
+        .ok()
+        .and_then(|attempt| Uuid::parse_str(attempt).ok())
+        .ok_or_else(|| NodeError::InvalidArgument(format!("invalid attempt id: {attempt:?}")))
+}
+
+impl MpcNode {
+    /// Runs `f` in a new MPC session with the other nodes.
+    async fn run_mpc<T, F>(&self, session_id: SessionId, f: F) -> NodeResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(Rep3MpcNet) -> eyre::Result<T> + Send + 'static,
+    {
         let time = Instant::now();
-        let result = tokio::task::spawn_blocking(|| {
-            tracing::info!("establishing network...");
-            let net = Rep3MpcNet::new(network_config)?;
-            tracing::info!("success!");
-            Self::sample_root_rand(net, commit_circuit)
-        })
-        .await
-        .expect("can join");
+        let result = self.sessions.run(session_id, f).await;
         let elapsed = time.elapsed();
         tracing::info!(
-            "took {}.{} in total",
+            "total MPC work {}.{}",
             elapsed.as_secs(),
             elapsed.subsec_nanos()
         );
+        result
+    }
 
-        let result = match result {
-            Ok(result) => result,
-            Err(err) => {
-                tracing::error!("{err:#?}");
-                return Err(tonic::Status::internal("checks logs something broke"));
+    /// Runs a game step as a job of the write-ahead log. The job is recorded
+    /// before the MPC starts and completes in the transaction storing the
+    /// result of the step. If the step fails before, its job is rolled back.
+    async fn run_job<T>(
+        &self,
+        game_id: Uuid,
+        phase: GamePhase,
+        input: Option<GameState>,
+        step: impl Future<Output = NodeResult<T>>,
+    ) -> NodeResult<T> {
+        self.db_store
+            .begin_job(game_id, phase, input.as_ref())
+            .await
+            .map_err(NodeError::db)?;
+        let result = step.await;
+        self.stages.close(game_id, phase);
+        if result.is_err() {
+            let rolled_back = self.db_store.fail_job(game_id, phase).await;
+            if let Err(err) = rolled_back {
+                tracing::error!("cannot roll back job {phase} of game {game_id}: {err:#}");
             }
+        }
+        result
+    }
+
+    /// Answers a request changing the state of the node at most once per
+    /// request id. The request reserves its id before `handle` runs, a
+    /// repeated request waits for the first one and gets its stored
+    /// response, without running `handle` again. If the first request fails,
+    /// its id is released and the repeated one runs. `step` is the game step
+    /// the request runs, rolling it back drops the stored response.
+    async fn idempotent<R, T>(
+        &self,
+        request: &R,
+        step: Option<(Uuid, GamePhase)>,
+        handle: impl Future<Output = NodeResult<T>>,
+    ) -> NodeResult<T>
+    where
+        R: IdempotentRequest,
+        T: Message + Default,
+    {
+        let method = R::METHOD;
+        let request_id = request.request_id();
+        if request_id.is_empty() {
+            return Err(NodeError::InvalidArgument(format!(
+                "{method} request without request id"
+            )));
+        }
+        let request_hash = request.request_hash();
+        let reused = || {
+            NodeError::InvalidArgument(format!(
+                "request id {request_id} was used for another {method} request"
+            ))
         };
-        let seed_c = self.db_store.store_root_rand(result).await.unwrap();
-        Ok(tonic::Response::new(SampleRandResponse { seed_c }))
+        let reserved = loop {
+            let reservation = self
+                .db_store
+                .reserve_response(method, request_id, &request_hash, step)
+                .await
+                .map_err(NodeError::db)?;
+            match reservation {
+                Reservation::Reserved(reserved) => break reserved,
+                Reservation::Pending {
+                    request_hash: stored,
+                } => {
+                    if stored != request_hash {
+                        return Err(reused());
+                    }
+                    tracing::debug!("waiting for running {method} request {request_id}");
+                    tokio::time::sleep(RESPONSE_POLL_INTERVAL).await;
+                }
+                Reservation::Done(stored) => {
+                    if stored.request_hash != request_hash {
+                        return Err(reused());
+                    }
+                    tracing::info!("answering repeated {method} request {request_id}");
+                    if let Some((game_id, phase)) = step {
+                        self.stages.replay(game_id, phase);
+                    }
+                    return T::decode(stored.response.as_slice())
+                        .map_err(|err| NodeError::Internal(err.into()));
+                }
+            }
+        };
+        let response = handle.await?;
+        // the request is done at this point, the caller gets its response
+        // even if it cannot be repeated
+        let stored = reserved.store(&response.encode_to_vec()).await;
+        if let Err(err) = stored {
+            tracing::error!("cannot store response of {method} request {request_id}: {err:#}");
+        }
+        Ok(response)
     }
-    async fn init_game(
+
+    /// Verifies the proof we computed before anything of it is stored or
+    /// returned.
+    async fn verify_proof(
         &self,
-        _: tonic::Request<InitGameRequest>,
-    ) -> std::result::Result<tonic::Response<InitGameResponse>, tonic::Status> {
-        let network_config = self.config.network_config().unwrap();
-        let init_circuit = self.init_circuit.clone();
-        let crs = Arc::clone(&self.crs);
-        let root_randomess = self
-            .db_store
-            .load_root_rand()
-            .await
-            .expect("can load from DB");
-        // we need to execute the init circuit
-        // The network can't run in tokio runtime because it creates a
-        // runtime internally. Therefore we need to do this
-        // roundtrip to sync land and back
+        proof: &HonkProof<ark_bn254::Fr>,
+        vk: &Arc<VerifyingKey<Bn254>>,
+        circuit: &'static str,
+        stages: &StageReporter,
+    ) -> NodeResult<()> {
         let time = Instant::now();
-        let result = tokio::task::spawn_blocking(|| {
-            tracing::info!("establishing network...");
-            let net = Rep3MpcNet::new(network_config)?;
-            tracing::info!("success!");
-            Self::init_game(crs, net, root_randomess, init_circuit)
+        let proof = proof.clone();
+        let vk = VerifyingKey::clone(vk);
+        let verified = tokio::task::spawn_blocking(move || {
+            UltraHonk::<_, Poseidon2Sponge>::verify(proof, vk, PROOF_ZK)
         })
         .await
-        .expect("can join");
-        let elapsed = time.elapsed();
-        tracing::info!(
-            "total MPC work {}.{}",
-            elapsed.as_secs(),
-            elapsed.subsec_nanos()
-        );
+        .map_err(|err| NodeError::Internal(err.into()))?
+        .map_err(|err| NodeError::Internal(err.into()))?;
+        stages.report(Stage::Verify, time.elapsed());
+        if !verified {
+            return Err(NodeError::InvalidProof(circuit));
+        }
+        Ok(())
+    }
 
-        let result = match result {
-            Ok(result) => result,
-            Err(err) => {
-                tracing::error!("{err:#?}");
-                return Err(tonic::Status::internal("checks logs something broke"));
-            }
-        };
-        let serialized = self
+    /// Loads what the choose and switch circuits need besides the player input.
+    async fn load_game(&self, game_id: Uuid) -> NodeResult<(GameState, NullifyingKey)> {
+        let game_state = self
             .db_store
-            .init_monty_hall(result)
+            .load_game_state(game_id)
             .await
-            .expect("can store into DB");
-        Ok(tonic::Response::new(InitGameResponse {
-            proof: serialized.proof,
-            game_state_c: serialized.game_state_c,
+            .map_err(NodeError::db)?
+            .ok_or(NodeError::NoGameState(game_id))?;
+        let nullifying_key = self
+            .db_store
+            .load_nullifying_key(game_id)
+            .await
+            .map_err(NodeError::db)?
+            .ok_or(NodeError::NoNullifyingKey(game_id))?;
+        Ok((game_state, nullifying_key))
+    }
+}
+
+#[async_trait]
+impl MpcNodeService for MpcNode {
+    async fn get_public_key(
+        &self,
+        _: tonic::Request<GetPublicKeyRequest>,
+    ) -> Result<tonic::Response<GetPublicKeyResponse>, tonic::Status> {
+        Ok(tonic::Response::new(GetPublicKeyResponse {
+            public_key: self.crypto_device.public_key().as_bytes().to_vec(),
         }))
     }
+    async fn sample_rand(
+        &self,
+        request: tonic::Request<SampleRandRequest>,
+    ) -> Result<tonic::Response<SampleRandResponse>, tonic::Status> {
+        let attempt = session_attempt(&request)?;
+        let request = request.into_inner();
+        let game_id = parse_game_id(&request.game_id)?;
+        let step = Some((game_id, GamePhase::Sampled));
+        let response = self
+            .idempotent(&request, step, async {
+                let _refresh = self.refresh_lock.read().await;
+                self.db_store
+                    .check_phase(game_id, GamePhase::Sampled)
+                    .await
+                    .map_err(NodeError::db)?;
+                let commit_circuit = self.commit_circuit.clone();
+                let stages = self.stages.reporter(game_id, GamePhase::Sampled);
+                tracing::info!("Started to sample root randomness!");
+                let seed_c = self
+                    .run_job(game_id, GamePhase::Sampled, None, async {
+                        // we need to sample some randomness and commit to it in MPC
+                        let result = self
+                            .run_mpc(SessionId::new(game_id, Step::SampleRand, attempt), |net| {
+                                Self::sample_root_rand(net, commit_circuit, stages)
+                            })
+                            .await?;
+                        self.db_store
+                            .store_root_rand(game_id, result)
+                            .await
+                            .map_err(NodeError::db)
+                    })
+                    .await?;
+                Ok(SampleRandResponse { seed_c })
+            })
+            .await?;
+        Ok(tonic::Response::new(response))
+    }
+    async fn generate_nullifying_key(
+        &self,
+        request: tonic::Request<GenerateNullifyingKeyRequest>,
+    ) -> Result<tonic::Response<GenerateNullifyingKeyResponse>, tonic::Status> {
+        let attempt = session_attempt(&request)?;
+        let request = request.into_inner();
+        let game_id = parse_game_id(&request.game_id)?;
+        let step = Some((game_id, GamePhase::KeyGenerated));
+        let response = self
+            .idempotent(&request, step, async {
+                let _refresh = self.refresh_lock.read().await;
+                self.db_store
+                    .check_phase(game_id, GamePhase::KeyGenerated)
+                    .await
+                    .map_err(NodeError::db)?;
+                let nullifying_key_circuit = self.nullifying_key_circuit.clone();
+                let stages = self.stages.reporter(game_id, GamePhase::KeyGenerated);
+                tracing::info!("Started to generate nullifying key!");
+                let (pk_x, pk_y, pk_is_infinite) = self
+                    .run_job(game_id, GamePhase::KeyGenerated, None, async {
+                        let result = self
+                            .run_mpc(
+                                SessionId::new(game_id, Step::NullifyingKey, attempt),
+                                |net| {
+                                    Self::generate_nullifying_key(
+                                        net,
+                                        nullifying_key_circuit,
+                                        stages,
+                                    )
+                                },
+                            )
+                            .await?;
+                        self.db_store
+                            .store_nullifying_key(game_id, &result)
+                            .await
+                            .map_err(NodeError::db)?;
+                        Ok(result.pk)
+                    })
+                    .await?;
+                let mut response = GenerateNullifyingKeyResponse {
+                    pk_x: Vec::new(),
+                    pk_y: Vec::new(),
+                    pk_is_infinite,
+                };
+                pk_x.serialize_compressed(&mut response.pk_x)
+                    .map_err(|err| NodeError::Internal(err.into()))?;
+                pk_y.serialize_compressed(&mut response.pk_y)
+                    .map_err(|err| NodeError::Internal(err.into()))?;
+                Ok(response)
+            })
+            .await?;
+        Ok(tonic::Response::new(response))
+    }
+    async fn init_game(
+        &self,
+        request: tonic::Request<InitGameRequest>,
+    ) -> std::result::Result<tonic::Response<InitGameResponse>, tonic::Status> {
+        let attempt = session_attempt(&request)?;
+        let request = request.into_inner();
+        let game_id = parse_game_id(&request.game_id)?;
+        let step = Some((game_id, GamePhase::Started));
+        let response = self
+            .idempotent(&request, step, async {
+                let _refresh = self.refresh_lock.read().await;
+                self.db_store
+                    .check_phase(game_id, GamePhase::Started)
+                    .await
+                    .map_err(NodeError::db)?;
+                let init_circuit = self.init_circuit.clone();
+                let stages = self.stages.reporter(game_id, GamePhase::Started);
+                let mpc_stages = stages.clone();
+                let crs = Arc::clone(&self.crs);
+                let root_randomess = self
+                    .db_store
+                    .load_root_rand(game_id)
+                    .await
+                    .map_err(NodeError::db)?
+                    .ok_or(NodeError::NoRootRand(game_id))?;
+                let serialized = self
+                    .run_job(game_id, GamePhase::Started, None, async {
+                        // we need to execute the init circuit
+                        let result = self
+                            .run_mpc(SessionId::new(game_id, Step::InitGame, attempt), |net| {
+                                Self::init_game(crs, net, root_randomess, init_circuit, mpc_stages)
+                            })
+                            .await?;
+                        self.verify_proof(&result.proof, &self.init_vk, "init", &stages)
+                            .await?;
+                        self.db_store
+                            .init_monty_hall(game_id, result)
+                            .await
+                            .map_err(NodeError::db)
+                    })
+                    .await?;
+                Ok(InitGameResponse {
+                    proof: serialized.proof,
+                    game_state_c: serialized.game_state_c,
+                })
+            })
+            .await?;
+        Ok(tonic::Response::new(response))
+    }
     async fn reveal_door(
         &self,
-        _: tonic::Request<RevealDoorRequest>,
+        request: tonic::Request<RevealDoorRequest>,
     ) -> Result<tonic::Response<RevealDoorResponse>, tonic::Status> {
-        todo!()
+        let attempt = session_attempt(&request)?;
+        let request = request.into_inner();
+        let game_id = parse_game_id(&request.game_id)?;
+        let step = Some((game_id, GamePhase::OpenedDoor));
+        let response = self
+            .idempotent(&request, step, async {
+                let _refresh = self.refresh_lock.read().await;
+                self.db_store
+                    .check_phase(game_id, GamePhase::OpenedDoor)
+                    .await
+                    .map_err(NodeError::db)?;
+                let door_choice = DoorChoice::decrypt(&request, &self.crypto_device)
+                    .map_err(|err| NodeError::InvalidArgument(err.to_string()))?;
+                let player_public_key = crypto_box::PublicKey::from_slice(
+                    &request.player_public_key,
+                )
+                .map_err(|_| NodeError::InvalidArgument("invalid player public key".to_owned()))?;
+                let choose_circuit = self.choose_circuit.clone();
+                let stages = self.stages.reporter(game_id, GamePhase::OpenedDoor);
+                let mpc_stages = stages.clone();
+                let crs = Arc::clone(&self.crs);
+                let (game_state, nullifying_key) = self.load_game(game_id).await?;
+                let input = Some(game_state.clone());
+                let (serialized, opened_door_ciphertext) = self
+                    .run_job(game_id, GamePhase::OpenedDoor, input, async {
+                        // we need to execute the choose circuit
+                        let result = self
+                            .run_mpc(SessionId::new(game_id, Step::RevealDoor, attempt), |net| {
+                                Self::reveal_door(
+                                    crs,
+                                    net,
+                                    game_state,
+                                    nullifying_key,
+                                    door_choice,
+                                    choose_circuit,
+                                    mpc_stages,
+                                )
+                            })
+                            .await?;
+                        self.verify_proof(&result.proof, &self.choose_vk, "choose", &stages)
+                            .await?;
+                        let opened_door_ciphertext = self
+                            .crypto_device
+                            .encrypt_player_output(
+                                &player_public_key,
+                                &(result.opened_door.clone(), result.opened_door_r.clone()),
+                            )
+                            .map_err(NodeError::from)?;
+                        let serialized = self
+                            .db_store
+                            .reveal_door(game_id, result)
+                            .await
+                            .map_err(NodeError::db)?;
+                        Ok((serialized, opened_door_ciphertext))
+                    })
+                    .await?;
+                Ok(RevealDoorResponse {
+                    proof: serialized.proof,
+                    new_game_state_c: serialized.game_state_c,
+                    opened_door_c: serialized.opened_door_c,
+                    game_state_nullifier: serialized.game_state_nullifier,
+                    opened_door_ciphertext,
+                })
+            })
+            .await?;
+        Ok(tonic::Response::new(response))
+    }
+    async fn finish_game(
+        &self,
+        request: tonic::Request<FinishGameRequest>,
+    ) -> Result<tonic::Response<FinishGameResponse>, tonic::Status> {
+        let attempt = session_attempt(&request)?;
+        let request = request.into_inner();
+        let game_id = parse_game_id(&request.game_id)?;
+        let step = Some((game_id, GamePhase::Done));
+        let response = self
+            .idempotent(&request, step, async {
+                let _refresh = self.refresh_lock.read().await;
+                self.db_store
+                    .check_phase(game_id, GamePhase::Done)
+                    .await
+                    .map_err(NodeError::db)?;
+                let switch_choice = SwitchChoice::decrypt(&request, &self.crypto_device)
+                    .map_err(|err| NodeError::InvalidArgument(err.to_string()))?;
+                let switch_circuit = self.switch_circuit.clone();
+                let stages = self.stages.reporter(game_id, GamePhase::Done);
+                let mpc_stages = stages.clone();
+                let crs = Arc::clone(&self.crs);
+                let (game_state, nullifying_key) = self.load_game(game_id).await?;
+                let input = Some(game_state.clone());
+                let serialized = self
+                    .run_job(game_id, GamePhase::Done, input, async {
+                        // we need to execute the switch circuit
+                        let result = self
+                            .run_mpc(SessionId::new(game_id, Step::FinishGame, attempt), |net| {
+                                Self::finish_game(
+                                    crs,
+                                    net,
+                                    game_state,
+                                    nullifying_key,
+                                    switch_choice,
+                                    switch_circuit,
+                                    mpc_stages,
+                                )
+                            })
+                            .await?;
+                        self.verify_proof(&result.proof, &self.switch_vk, "switch", &stages)
+                            .await?;
+                        self.db_store
+                            .finish_game(game_id, result)
+                            .await
+                            .map_err(NodeError::db)
+                    })
+                    .await?;
+                Ok(FinishGameResponse {
+                    proof: serialized.proof,
+                    win_c: serialized.win_c,
+                    game_state_nullifier: serialized.game_state_nullifier,
+                })
+            })
+            .await?;
+        Ok(tonic::Response::new(response))
+    }
+    async fn refresh_shares(
+        &self,
+        request: tonic::Request<RefreshSharesRequest>,
+    ) -> Result<tonic::Response<RefreshSharesResponse>, tonic::Status> {
+        let attempt = session_attempt(&request)?;
+        let request = request.into_inner();
+        let response = self
+            .idempotent(&request, None, async {
+                let _refresh = self.refresh_lock.write().await;
+                // refuse before the MPC, such that the other nodes abort too
+                self.db_store
+                    .check_no_running_jobs()
+                    .await
+                    .map_err(NodeError::db)?;
+                tracing::info!("Started to refresh shares!");
+                let rows = self.db_store.load_rows().await.map_err(NodeError::db)?;
+                let refreshed_games = rows
+                    .iter()
+                    .map(|row| row.game_id)
+                    .collect::<HashSet<_>>()
+                    .len() as u64;
+                let rows = self
+                    .run_mpc(
+                        SessionId::new(Uuid::nil(), Step::RefreshShares, attempt),
+                        |net| refresh::refresh_shares(net, rows),
+                    )
+                    .await?;
+                self.db_store
+                    .store_shares(&rows)
+                    .await
+                    .map_err(NodeError::db)?;
+                tracing::info!("refreshed the shares of {refreshed_games} games");
+                Ok(RefreshSharesResponse { refreshed_games })
+            })
+            .await?;
+        Ok(tonic::Response::new(response))
+    }
+    async fn list_jobs(
+        &self,
+        _: tonic::Request<ListJobsRequest>,
+    ) -> Result<tonic::Response<ListJobsResponse>, tonic::Status> {
+        let jobs = self
+            .db_store
+            .load_jobs()
+            .await
+            .map_err(NodeError::db)?
+            .into_iter()
+            .map(|job| {
+                let status = match job.status {
+                    JobStatus::Running => ProtoJobStatus::Running,
+                    JobStatus::Completed => ProtoJobStatus::Completed,
+                    JobStatus::RolledBack => ProtoJobStatus::RolledBack,
+                };
+                let mut proto = Job {
+                    game_id: job.game_id.to_string(),
+                    output: job.output,
+                    age_secs: job.age.as_secs(),
+                    ..Default::default()
+                };
+                proto.set_phase(job.phase.into());
+                proto.set_status(status);
+                proto
+            })
+            .collect();
+        Ok(tonic::Response::new(ListJobsResponse { jobs }))
+    }
+    async fn rollback_job(
+        &self,
+        request: tonic::Request<RollbackJobRequest>,
+    ) -> Result<tonic::Response<RollbackJobResponse>, tonic::Status> {
+        let request = request.into_inner();
+        let game_id = parse_game_id(&request.game_id)?;
+        let phase = ProtoGamePhase::try_from(request.phase)
+            .map_err(|_| NodeError::InvalidArgument("invalid game phase".to_owned()))?;
+        let response = self
+            .idempotent(&request, None, async {
+                // no step may run while one is undone
+                let _refresh = self.refresh_lock.write().await;
+                self.db_store
+                    .rollback_job(game_id, phase.into())
+                    .await
+                    .map_err(NodeError::db)?;
+                Ok(RollbackJobResponse {})
+            })
+            .await?;
+        Ok(tonic::Response::new(response))
+    }
+    type WatchJobStream = StageWatch;
+    async fn watch_job(
+        &self,
+        request: tonic::Request<WatchJobRequest>,
+    ) -> Result<tonic::Response<Self::WatchJobStream>, tonic::Status> {
+        let game_id = parse_game_id(&request.get_ref().game_id)?;
+        let phase = ProtoGamePhase::try_from(request.get_ref().phase)
+            .map_err(|_| NodeError::InvalidArgument("invalid game phase".to_owned()))?;
+        let watch = self.stages.watch(game_id, phase.into());
+        Ok(tonic::Response::new(watch))
     }
 }
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak, mpsc};
+use std::time::{Duration, Instant};
+
+use ark_ff::{BigInteger as _, PrimeField as _};
+use co_noir::Rep3MpcNet;
+use eyre::Context as _;
+use mpc_core::protocols::rep3::id::PartyID;
+use mpc_core::protocols::rep3::network::Rep3Network;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::config::NodeConfig;
+use crate::error::{NodeError, NodeResult};
+
+/// How long a session waits for the other nodes to open it, and how long
+/// channels opened by the other nodes wait for the local request.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long the MPC of a session may run once all nodes opened it.
+const MPC_TIMEOUT: Duration = Duration::from_secs(600);
+/// How often channels no local request picked up are dropped.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(5);
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// The MPC step of a game a session runs. Every step runs at most once per
+/// game at a time, so together with the game id and the attempt id the
+/// orchestrator sends along it identifies the session on all nodes without
+/// further coordination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u64)]
+pub(crate) enum Step {
+    SampleRand = 0,
+    NullifyingKey = 1,
+    InitGame = 2,
+    RevealDoor = 3,
+    FinishGame = 4,
+    /// Refreshes the shares of all games, runs with the nil game id.
+    RefreshShares = 5,
+}
+
+impl TryFrom<u64> for Step {
+    type Error = eyre::Report;
+
+    fn try_from(value: u64) -> eyre::Result<Self> {
+        Ok(match value {
+            0 => Step::SampleRand,
+            1 => Step::NullifyingKey,
+            2 => Step::InitGame,
+            3 => Step::RevealDoor,
+            4 => Step::FinishGame,
+            5 => Step::RefreshShares,
+            _ => eyre::bail!("unknown step {value}"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct SessionId {
+    pub(crate) game_id: Uuid,
+    pub(crate) step: Step,
+    /// Differs between attempts of the same step, such that a retry never
+    /// joins the channels of an aborted attempt.
+    pub(crate) attempt: Uuid,
+}
+
+impl SessionId {
+    pub(crate) fn new(game_id: Uuid, step: Step, attempt: Uuid) -> Self {
+        Self {
+            game_id,
+            step,
+            attempt,
+        }
+    }
+
+    fn to_fields(self) -> [ark_bn254::Fr; 3] {
+        [
+            ark_bn254::Fr::from(self.game_id.as_u128()),
+            ark_bn254::Fr::from(self.step as u64),
+            ark_bn254::Fr::from(self.attempt.as_u128()),
+        ]
+    }
+
+    fn from_fields(fields: &[ark_bn254::Fr]) -> eyre::Result<Self> {
+        let [game_id, step, attempt] = fields else {
+            eyre::bail!("session id must be three field elements");
+        };
+        let to_uuid = |field: &ark_bn254::Fr| {
+            let bytes = field.into_bigint().to_bytes_le();
+            Uuid::from_u128(u128::from_le_bytes(
+                bytes[..16].try_into().expect("field has 32 bytes"),
+            ))
+        };
+        Ok(Self {
+            game_id: to_uuid(game_id),
+            step: Step::try_from(step.into_bigint().0[0])?,
+            attempt: to_uuid(attempt),
+        })
+    }
+}
+
+enum Slot {
+    /// The local request waits for the other nodes.
+    Waiting(oneshot::Sender<Rep3MpcNet>),
+    /// The other nodes opened the session before the local request arrived.
+    Ready(Rep3MpcNet, Instant),
+}
+
+type Slots = Arc<Mutex<HashMap<SessionId, Slot>>>;
+
+/// Runs several MPC sessions with the other nodes in parallel over
+/// long-lived connections.
+///
+/// Every session gets its own channels forked off the connections. Forking
+/// is a joint operation of all nodes, so they have to fork in the same
+/// order: node 0 leads and announces every session it opens to the other
+/// nodes over the long-lived connections. The others fork on announcement
+/// and hand the channels to their local request for the same session, which
+/// may arrive before or after.
+///
+/// Channels no local request picks up within [`SESSION_TIMEOUT`] are
+/// dropped, e.g. if the local node refused the step before its MPC, such
+/// that the other nodes of the session fail instead of waiting for us.
+///
+/// If the connections fail, all pending sessions fail and the connections
+/// are re-established.
+pub(crate) struct NetworkSessions {
+    slots: Slots,
+    /// The sessions to announce, only read if we are node 0.
+    announce: mpsc::Sender<SessionId>,
+    is_leader: bool,
+}
+
+impl NetworkSessions {
+    /// Spawns the thread managing the connections. They are established in
+    /// the background, as the other nodes may not be up yet.
+    pub(crate) fn new(config: Arc<NodeConfig>) -> eyre::Result<Self> {
+        let is_leader = config
+            .network_config()
+            .context("while reading network config")?
+            .my_id
+            == 0;
+        let slots = Slots::default();
+        let (announce, announcements) = mpsc::channel();
+        let dispatcher_slots = Arc::clone(&slots);
+        std::thread::Builder::new()
+            .name("mpc-sessions".to_owned())
+            .spawn(move || Self::dispatch(&config, is_leader, announcements, dispatcher_slots))?;
+        let pruned_slots = Arc::downgrade(&slots);
+        std::thread::Builder::new()
+            .name("mpc-sessions-prune".to_owned())
+            .spawn(move || Self::prune(pruned_slots))?;
+        Ok(Self {
+            slots,
+            announce,
+            is_leader,
+        })
+    }
+
+    /// Runs `f` in the session `session_id`, once all nodes opened it.
+    ///
+    /// Fails if `f` does not finish within [`MPC_TIMEOUT`]. `f` keeps
+    /// running until its channels fail then, but the request, and the locks
+    /// it holds, are released.
+    pub(crate) async fn run<T, F>(&self, session_id: SessionId, f: F) -> NodeResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(Rep3MpcNet) -> eyre::Result<T> + Send + 'static,
+    {
+        let net = self.open(session_id).await.map_err(NodeError::Mpc)?;
+        // The network can't run in tokio runtime because it creates a
+        // runtime internally. Therefore we need to do this
+        // roundtrip to sync land and back
+        let mpc = tokio::task::spawn_blocking(move || f(net));
+        match tokio::time::timeout(MPC_TIMEOUT, mpc).await {
+            Ok(result) => result
+                .context("while joining MPC task")?
+                .map_err(NodeError::Mpc),
+            Err(_) => Err(NodeError::Mpc(eyre::eyre!(
+                "MPC of session {session_id:?} did not finish in time"
+            ))),
+        }
+    }
+
+    async fn open(&self, session_id: SessionId) -> eyre::Result<Rep3MpcNet> {
+        let rx = {
+            let mut slots = self.slots.lock().expect("not poisoned");
+            match slots.remove(&session_id) {
+                Some(Slot::Ready(net, _)) => return Ok(net),
+                Some(waiting @ Slot::Waiting(_)) => {
+                    slots.insert(session_id, waiting);
+                    eyre::bail!("session {session_id:?} is already running")
+                }
+                None => {
+                    let (tx, rx) = oneshot::channel();
+                    slots.insert(session_id, Slot::Waiting(tx));
+                    rx
+                }
+            }
+        };
+        if self.is_leader {
+            self.announce
+                .send(session_id)
+                .map_err(|_| eyre::eyre!("network sessions are shut down"))?;
+        }
+        match tokio::time::timeout(SESSION_TIMEOUT, rx).await {
+            Ok(Ok(net)) => Ok(net),
+            Ok(Err(_)) => eyre::bail!("connections to the other nodes failed"),
+            Err(_) => {
+                self.slots.lock().expect("not poisoned").remove(&session_id);
+                eyre::bail!("the other nodes did not open session {session_id:?} in time")
+            }
+        }
+    }
+
+    fn dispatch(
+        config: &NodeConfig,
+        is_leader: bool,
+        announcements: mpsc::Receiver<SessionId>,
+        slots: Slots,
+    ) {
+        loop {
+            let result = Self::connect(config)
+                .and_then(|mut root| Self::serve(&mut root, is_leader, &announcements, &slots));
+            match result {
+                Ok(()) => return,
+                Err(err) => tracing::error!("MPC connections failed: {err:#}"),
+            }
+            // the pending sessions lost their counterparts
+            slots.lock().expect("not poisoned").clear();
+            std::thread::sleep(RECONNECT_DELAY);
+        }
+    }
+
+    /// Drops the channels no local request picked up in time, until the
+    /// sessions are dropped.
+    fn prune(slots: Weak<Mutex<HashMap<SessionId, Slot>>>) {
+        loop {
+            std::thread::sleep(PRUNE_INTERVAL);
+            let Some(slots) = slots.upgrade() else {
+                return;
+            };
+            slots
+                .lock()
+                .expect("not poisoned")
+                .retain(|session_id, slot| match slot {
+                    Slot::Ready(_, opened) if opened.elapsed() >= SESSION_TIMEOUT => {
+                        tracing::warn!("dropping session {session_id:?} no request picked up");
+                        false
+                    }
+                    Slot::Ready(..) => true,
+                    Slot::Waiting(tx) => !tx.is_closed(),
+                });
+        }
+    }
+
+    fn connect(config: &NodeConfig) -> eyre::Result<Rep3MpcNet> {
+        let network_config = config
+            .network_config()
+            .context("while reading network config")?;
+        tracing::info!("establishing network...");
+        let net = Rep3MpcNet::new(network_config)?;
+        tracing::info!("success!");
+        Ok(net)
+    }
+
+    /// Opens sessions until the connections fail. Returns `Ok` if the node
+    /// shuts down.
+    fn serve(
+        root: &mut Rep3MpcNet,
+        is_leader: bool,
+        announcements: &mpsc::Receiver<SessionId>,
+        slots: &Slots,
+    ) -> eyre::Result<()> {
+        loop {
+            let session_id = if is_leader {
+                let Ok(session_id) = announcements.recv() else {
+                    return Ok(());
+                };
+                if !matches!(
+                    slots.lock().expect("not poisoned").get(&session_id),
+                    Some(Slot::Waiting(_))
+                ) {
+                    // the request gave up while we were reconnecting
+                    continue;
+                }
+                let fields = session_id.to_fields();
+                root.send_many(PartyID::ID1, &fields)?;
+                root.send_many(PartyID::ID2, &fields)?;
+                session_id
+            } else {
+                SessionId::from_fields(&root.recv_many::<ark_bn254::Fr>(PartyID::ID0)?)?
+            };
+            let net = root.fork().context("while forking network")?;
+            tracing::debug!("opened session {session_id:?}");
+
+            let mut slots = slots.lock().expect("not poisoned");
+            match slots.remove(&session_id) {
+                Some(Slot::Waiting(tx)) => {
+                    // if the request gave up in the meantime, the other
+                    // nodes notice when the channels are dropped
+                    let _ = tx.send(net);
+                }
+                _ => {
+                    slots.insert(session_id, Slot::Ready(net, Instant::now()));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use std::fmt;
+
+use uuid::Uuid;
+
+/// The phase of a game on this node, stored in the `phase` column of
+/// `root_rand`. A game without root randomness has no phase yet.
+///
+/// The phases follow the steps the orchestration runs, every step moves a
+/// game one phase further:
+///
+/// `sample_rand` -> [`GamePhase::Sampled`] -> `generate_nullifying_key` ->
+/// [`GamePhase::KeyGenerated`] -> `init_game` -> [`GamePhase::Started`] ->
+/// `reveal_door` -> [`GamePhase::OpenedDoor`] -> `finish_game` ->
+/// [`GamePhase::Done`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum GamePhase {
+    Sampled,
+    KeyGenerated,
+    Started,
+    OpenedDoor,
+    Done,
+}
+
+/// A step was requested for a game that is not in the phase before it.
+#[derive(Debug, thiserror::Error)]
+#[error("game {game_id} is {} and cannot move to {next}", phase_name(.phase))]
+pub(crate) struct InvalidTransition {
+    pub(crate) game_id: Uuid,
+    pub(crate) phase: Option<GamePhase>,
+    pub(crate) next: GamePhase,
+}
+
+impl GamePhase {
+    pub(crate) fn tag(self) -> &'static str {
+        match self {
+            GamePhase::Sampled => "sampled",
+            GamePhase::KeyGenerated => "key_generated",
+            GamePhase::Started => "started",
+            GamePhase::OpenedDoor => "opened_door",
+            GamePhase::Done => "done",
+        }
+    }
+
+    pub(crate) fn from_tag(tag: &str) -> eyre::Result<Self> {
+        match tag {
+            "sampled" => Ok(GamePhase::Sampled),
+            "key_generated" => Ok(GamePhase::KeyGenerated),
+            "started" => Ok(GamePhase::Started),
+            "opened_door" => Ok(GamePhase::OpenedDoor),
+            "done" => Ok(GamePhase::Done),
+            _ => eyre::bail!("unknown game phase {tag}"),
+        }
+    }
+
+    /// The phase a game must be in to move to this phase.
+    pub(crate) fn previous(self) -> Option<GamePhase> {
+        match self {
+            GamePhase::Sampled => None,
+            GamePhase::KeyGenerated => Some(GamePhase::Sampled),
+            GamePhase::Started => Some(GamePhase::KeyGenerated),
+            GamePhase::OpenedDoor => Some(GamePhase::Started),
+            GamePhase::Done => Some(GamePhase::OpenedDoor),
+        }
+    }
+
+    /// Checks that a game in `phase` may move to `next`.
+    pub(crate) fn check_transition(
+        game_id: Uuid,
+        phase: Option<GamePhase>,
+        next: GamePhase,
+    ) -> Result<(), InvalidTransition> {
+        if phase != next.previous() {
+            return Err(InvalidTransition {
+                game_id,
+                phase,
+                next,
+            });
+        }
+        Ok(())
+    }
+}
+
+fn phase_name(phase: &Option<GamePhase>) -> &'static str {
+    phase.map_or("not sampled", GamePhase::tag)
+}
+
+impl fmt::Display for GamePhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+impl From<GamePhase> for protos::monty_hall::GamePhase {
+    fn from(phase: GamePhase) -> Self {
+        match phase {
+            GamePhase::Sampled => Self::Sampled,
+            GamePhase::KeyGenerated => Self::KeyGenerated,
+            GamePhase::Started => Self::Started,
+            GamePhase::OpenedDoor => Self::OpenedDoor,
+            GamePhase::Done => Self::Done,
+        }
+    }
+}
+
+impl From<protos::monty_hall::GamePhase> for GamePhase {
+    fn from(phase: protos::monty_hall::GamePhase) -> Self {
+        match phase {
+            protos::monty_hall::GamePhase::Sampled => Self::Sampled,
+            protos::monty_hall::GamePhase::KeyGenerated => Self::KeyGenerated,
+            protos::monty_hall::GamePhase::Started => Self::Started,
+            protos::monty_hall::GamePhase::OpenedDoor => Self::OpenedDoor,
+            protos::monty_hall::GamePhase::Done => Self::Done,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::GamePhase;
+
+    const PHASES: [GamePhase; 5] = [
+        GamePhase::Sampled,
+        GamePhase::KeyGenerated,
+        GamePhase::Started,
+        GamePhase::OpenedDoor,
+        GamePhase::Done,
+    ];
+
+    #[test]
+    fn every_step_moves_one_phase() {
+        let mut phase = None;
+        for next in PHASES {
+            GamePhase::check_transition(Uuid::nil(), phase, next).unwrap();
+            phase = Some(next);
+        }
+    }
+
+    #[test]
+    fn reject_steps_out_of_order() {
+        let phases = std::iter::once(None).chain(PHASES.map(Some));
+        for (i, phase) in phases.enumerate() {
+            for (j, next) in PHASES.into_iter().enumerate() {
+                // `phase` is the i-th phase, counting the unsampled one
+                let valid = j == i;
+                assert_eq!(
+                    GamePhase::check_transition(Uuid::nil(), phase, next).is_ok(),
+                    valid,
+                    "{phase:?} to {next:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reject_repeated_step() {
+        let err =
+            GamePhase::check_transition(Uuid::nil(), Some(GamePhase::Started), GamePhase::Started)
+                .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("game {} is started and cannot move to started", Uuid::nil())
+        );
+    }
+
+    #[test]
+    fn reject_unsampled_game() {
+        let err =
+            GamePhase::check_transition(Uuid::nil(), None, GamePhase::KeyGenerated).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "game {} is not sampled and cannot move to key_generated",
+                Uuid::nil()
+            )
+        );
+    }
+
+    #[test]
+    fn tag_round_trip() {
+        for phase in PHASES {
+            assert_eq!(GamePhase::from_tag(phase.tag()).unwrap(), phase);
+        }
+        assert!(GamePhase::from_tag("unknown").is_err());
+    }
+}
@@ -0,0 +1,51 @@
+use co_noir::Rep3MpcNet;
+use mpc_core::protocols::rep3::Rep3PrimeFieldShare;
+use mpc_core::protocols::rep3::network::{IoContext, Rep3Network};
+
+use crate::data_store::StoredRow;
+
+/// Re-randomizes the shares of all rows without changing the shared
+/// values, so shares leaked before the refresh cannot be combined with
+/// shares leaked after it.
+///
+/// Every party adds its share of a fresh sharing of zero to its own share
+/// and passes the result to the next party, which holds it as its second
+/// share. The rows have to be ordered the same on all parties.
+pub(crate) fn refresh_shares(
+    network: Rep3MpcNet,
+    mut rows: Vec<StoredRow>,
+) -> eyre::Result<Vec<StoredRow>> {
+    tracing::info!("creating io context");
+    let mut io_context = IoContext::init(network)?;
+
+    // a share refreshed by only one party would be lost for good
+    let layout = rows.iter().flat_map(StoredRow::layout).collect::<Vec<_>>();
+    if io_context.network.reshare_many(&layout)? != layout {
+        eyre::bail!("the previous party stores other shares than we do");
+    }
+
+    let mut shares = rows
+        .iter_mut()
+        .flat_map(StoredRow::shares_mut)
+        .collect::<Vec<_>>();
+    tracing::info!("refreshing {} shares", shares.len());
+    let a = shares
+        .iter()
+        .map(|share| {
+            let (zero_a, zero_b) = io_context.random_fes::<ark_bn254::Fr>();
+            share.a + zero_a - zero_b
+        })
+        .collect::<Vec<_>>();
+    let b = io_context.network.reshare_many(&a)?;
+    if b.len() != a.len() {
+        eyre::bail!(
+            "the previous party refreshed {} shares, we {}",
+            b.len(),
+            a.len()
+        );
+    }
+    for ((share, a), b) in shares.iter_mut().zip(a).zip(b) {
+        **share = Rep3PrimeFieldShare::new(a, b);
+    }
+    Ok(rows)
+}
@@ -0,0 +1,145 @@
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+
+use protos::monty_hall::{Stage, StageEvent};
+use tokio::sync::broadcast;
+use tokio_stream::{
+    Stream,
+    wrappers::{BroadcastStream, errors::BroadcastStreamRecvError},
+};
+use uuid::Uuid;
+
+use crate::phase::GamePhase;
+
+/// How many events a slow watcher may lag behind before it misses some.
+const EVENT_CAPACITY: usize = 16;
+
+/// A job of a game step, keyed as in the job log.
+type JobKey = (Uuid, GamePhase);
+
+/// The stage events of the jobs someone watches. Jobs nobody watches
+/// report into the void.
+#[derive(Clone, Default)]
+pub(crate) struct StageEvents {
+    watched: Arc<Mutex<HashMap<JobKey, broadcast::Sender<StageEvent>>>>,
+}
+
+/// Reports the finished stages of one job. It is moved into the MPC of the
+/// step, which runs on a blocking thread.
+#[derive(Clone)]
+pub(crate) struct StageReporter {
+    events: StageEvents,
+    job: JobKey,
+}
+
+/// The stream of a `WatchJob` call. It ends when the job ends and stops
+/// watching when the caller hangs up.
+pub(crate) struct StageWatch {
+    events: StageEvents,
+    job: JobKey,
+    rx: Option<BroadcastStream<StageEvent>>,
+}
+
+impl StageEvents {
+    pub(crate) fn reporter(&self, game_id: Uuid, phase: GamePhase) -> StageReporter {
+        StageReporter {
+            events: self.clone(),
+            job: (game_id, phase),
+        }
+    }
+
+    /// Watches the job of a step, which may not have started yet.
+    pub(crate) fn watch(&self, game_id: Uuid, phase: GamePhase) -> StageWatch {
+        let job = (game_id, phase);
+        let rx = self
+            .watched
+            .lock()
+            .expect("not poisoned")
+            .entry(job)
+            .or_insert_with(|| broadcast::channel(EVENT_CAPACITY).0)
+            .subscribe();
+        StageWatch {
+            events: self.clone(),
+            job,
+            rx: Some(BroadcastStream::new(rx)),
+        }
+    }
+
+    /// Ends the streams of the watchers of a job, after the job ended.
+    pub(crate) fn close(&self, game_id: Uuid, phase: GamePhase) {
+        self.watched
+            .lock()
+            .expect("not poisoned")
+            .remove(&(game_id, phase));
+    }
+
+    /// Ends the streams of the watchers of a step answered from its stored
+    /// response, which runs no job. They get the last stage of the step,
+    /// which took no time this time.
+    pub(crate) fn replay(&self, game_id: Uuid, phase: GamePhase) {
+        self.reporter(game_id, phase)
+            .report(last_stage(phase), Duration::ZERO);
+        self.close(game_id, phase);
+    }
+}
+
+/// The last stage of the step moving a game to `phase`. The steps before
+/// the game starts prove nothing.
+fn last_stage(phase: GamePhase) -> Stage {
+    match phase {
+        GamePhase::Sampled | GamePhase::KeyGenerated => Stage::WitnessExtension,
+        GamePhase::Started | GamePhase::OpenedDoor | GamePhase::Done => Stage::Verify,
+    }
+}
+
+impl StageReporter {
+    pub(crate) fn report(&self, stage: Stage, duration: Duration) {
+        let watched = self.events.watched.lock().expect("not poisoned");
+        if let Some(tx) = watched.get(&self.job) {
+            let mut event = StageEvent {
+                duration_ms: duration.as_millis() as u64,
+                ..Default::default()
+            };
+            event.set_stage(stage);
+            // the last watcher may just have hung up
+            let _ = tx.send(event);
+        }
+    }
+}
+
+impl Stream for StageWatch {
+    type Item = Result<StageEvent, tonic::Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let Some(rx) = self.get_mut().rx.as_mut() else {
+            return Poll::Ready(None);
+        };
+        loop {
+            match ready!(Pin::new(&mut *rx).poll_next(cx)) {
+                Some(Ok(event)) => return Poll::Ready(Some(Ok(event))),
+                // the events are informational, a lagging watcher skips some
+                Some(Err(BroadcastStreamRecvError::Lagged(_))) => continue,
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl Drop for StageWatch {
+    fn drop(&mut self) {
+        drop(self.rx.take());
+        let mut watched = self.events.watched.lock().expect("not poisoned");
+        // forget a job nobody watches anymore, e.g. one that never started
+        if watched
+            .get(&self.job)
+            .is_some_and(|tx| tx.receiver_count() == 0)
+        {
+            watched.remove(&self.job);
+        }
+    }
+}
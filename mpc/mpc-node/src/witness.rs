@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use acir::AcirField as _;
+use acir::FieldElement;
+use acir::circuit::Opcode;
+use acir::circuit::brillig::BrilligInputs;
+use acir::native_types::Expression;
+use ark_ff::One as _;
+use co_noir::Rep3AcvmType;
+use eyre::Context as _;
+use mpc_core::protocols::rep3::{self, id::PartyID};
+use noirc_artifacts::program::ProgramArtifact;
+
+use crate::mpc::ArithmeticShare;
+
+/// The unconstrained function `PrivateState::new` calls to hand a freshly
+/// created private state to the MPC network (see `pss_utils`).
+const STORE_PRIVATE_STATE: &str = "store_private_state";
+
+type AcvmType = Rep3AcvmType<ark_bn254::Fr>;
+
+/// Locates the outputs of a circuit in its witness.
+///
+/// Noir assigns the first witnesses to the parameters of `main` in the order
+/// of the ABI, followed by the return values. Private states created with
+/// `PrivateState::new` are found through their call to
+/// `store_private_state`.
+pub(crate) struct WitnessLayout {
+    return_values: Range<usize>,
+    private_states: BTreeMap<String, Vec<Expression<FieldElement>>>,
+}
+
+impl WitnessLayout {
+    pub(crate) fn new(circuit: &ProgramArtifact) -> eyre::Result<Self> {
+        let offset = circuit
+            .abi
+            .parameters
+            .iter()
+            .map(|parameter| parameter.typ.field_count() as usize)
+            .sum::<usize>();
+        let return_len = circuit
+            .abi
+            .return_type
+            .as_ref()
+            .map(|return_type| return_type.abi_type.field_count() as usize)
+            .unwrap_or_default();
+
+        Ok(Self {
+            return_values: offset..offset + return_len,
+            private_states: Self::find_private_states(circuit)?,
+        })
+    }
+
+    fn find_private_states(
+        circuit: &ProgramArtifact,
+    ) -> eyre::Result<BTreeMap<String, Vec<Expression<FieldElement>>>> {
+        let main = circuit
+            .bytecode
+            .functions
+            .first()
+            .context("circuit has no main function")?;
+        let mut private_states = BTreeMap::new();
+        for opcode in main.opcodes.iter() {
+            let Opcode::BrilligCall {
+                id: function_id,
+                inputs,
+                ..
+            } = opcode
+            else {
+                continue;
+            };
+            if circuit
+                .brillig_names
+                .get(function_id.0 as usize)
+                .map(String::as_str)
+                != Some(STORE_PRIVATE_STATE)
+            {
+                continue;
+            }
+            let [BrilligInputs::Array(label), BrilligInputs::Array(data)] = inputs.as_slice()
+            else {
+                eyre::bail!("unexpected inputs for {STORE_PRIVATE_STATE}");
+            };
+            let name = decode_label(label)
+                .with_context(|| format!("{STORE_PRIVATE_STATE} called without a name"))?;
+            private_states.insert(name, data.to_owned());
+        }
+        Ok(private_states)
+    }
+
+    /// Returns the public outputs of the circuit. These are opened during
+    /// witness extension, so we fail if any of them is still shared.
+    pub(crate) fn public_outputs<const N: usize>(
+        &self,
+        witness: &[AcvmType],
+    ) -> eyre::Result<[ark_bn254::Fr; N]> {
+        if self.return_values.len() != N {
+            eyre::bail!(
+                "expected {N} return values but circuit has {}",
+                self.return_values.len()
+            );
+        }
+        let outputs = witness
+            .get(self.return_values.to_owned())
+            .context("witness is shorter than the ABI")?
+            .iter()
+            .map(|value| match value {
+                Rep3AcvmType::Public(value) => Ok(*value),
+                Rep3AcvmType::Shared(_) => eyre::bail!("public output is not opened"),
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+        Ok(outputs.try_into().expect("checked length above"))
+    }
+
+    /// Returns the shares of the private state the circuit hands out under
+    /// `name` via `PrivateState::new`. Public values are promoted to trivial
+    /// shares, so the result can directly serve as input for the next
+    /// circuit.
+    pub(crate) fn private_state(
+        &self,
+        witness: &[AcvmType],
+        name: &str,
+        id: PartyID,
+    ) -> eyre::Result<Vec<ArithmeticShare>> {
+        self.private_states
+            .get(name)
+            .with_context(|| format!("circuit does not store a private state named \"{name}\""))?
+            .iter()
+            .map(|expr| evaluate_linear(expr, witness, id))
+            .collect()
+    }
+}
+
+fn to_share(value: &AcvmType, id: PartyID) -> ArithmeticShare {
+    match value {
+        Rep3AcvmType::Shared(share) => share.to_owned(),
+        Rep3AcvmType::Public(value) => rep3::arithmetic::promote_to_trivial_share(id, *value),
+    }
+}
+
+fn decode_label(label: &[Expression<FieldElement>]) -> Option<String> {
+    label
+        .iter()
+        .map(|c| c.to_const()?.try_to_u32().and_then(char::from_u32))
+        .collect()
+}
+
+fn evaluate_linear(
+    expr: &Expression<FieldElement>,
+    witness: &[AcvmType],
+    id: PartyID,
+) -> eyre::Result<ArithmeticShare> {
+    if !expr.mul_terms.is_empty() {
+        eyre::bail!("private state must be a linear expression of the witness");
+    }
+    let mut result = rep3::arithmetic::promote_to_trivial_share(id, expr.q_c.into_repr());
+    for (coeff, w) in expr.linear_combinations.iter() {
+        let share = witness
+            .get(w.as_usize())
+            .with_context(|| format!("witness {} out of bounds", w.as_usize()))?;
+        let share = to_share(share, id);
+        let term = if coeff.into_repr().is_one() {
+            share
+        } else {
+            rep3::arithmetic::mul_public(share, coeff.into_repr())
+        };
+        result = rep3::arithmetic::add(result, term);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use co_noir::{Rep3AcvmType, Utils};
+    use mpc_core::protocols::rep3::{Rep3PrimeFieldShare, id::PartyID};
+    use noirc_abi::{AbiParameter, AbiReturnType, AbiType, AbiVisibility};
+    use noirc_artifacts::program::ProgramArtifact;
+
+    use super::{AcvmType, WitnessLayout};
+
+    fn circuit(name: &str) -> ProgramArtifact {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("data/circuits")
+            .join(format!("{name}.json"));
+        Utils::get_program_artifact_from_file(&path).unwrap()
+    }
+
+    fn public_witness(len: u64) -> Vec<AcvmType> {
+        (0..len)
+            .map(|i| Rep3AcvmType::Public(ark_bn254::Fr::from(i)))
+            .collect()
+    }
+
+    #[test]
+    fn return_value_follows_parameters() {
+        // seed, seed_r, seed_c and out_r
+        let layout = WitnessLayout::new(&circuit("monty_hall_init")).unwrap();
+        assert_eq!(
+            layout.public_outputs::<1>(&public_witness(8)).unwrap(),
+            [ark_bn254::Fr::from(4u64)]
+        );
+        // x and meta
+        let layout = WitnessLayout::new(&circuit("taceo_commit")).unwrap();
+        assert_eq!(
+            layout.public_outputs::<1>(&public_witness(8)).unwrap(),
+            [ark_bn254::Fr::from(2u64)]
+        );
+    }
+
+    #[test]
+    fn parameters_count_their_fields() {
+        let mut circuit = circuit("taceo_commit");
+        circuit.abi.parameters = vec![
+            AbiParameter {
+                name: "meta".to_owned(),
+                typ: AbiType::Struct {
+                    path: "pss_utils::private_state::CommitMetaData".to_owned(),
+                    fields: vec![(
+                        "data".to_owned(),
+                        AbiType::Array {
+                            length: 3,
+                            typ: Box::new(AbiType::Field),
+                        },
+                    )],
+                },
+                visibility: AbiVisibility::Private,
+            },
+            AbiParameter {
+                name: "x".to_owned(),
+                typ: AbiType::Field,
+                visibility: AbiVisibility::Public,
+            },
+        ];
+        circuit.abi.return_type = Some(AbiReturnType {
+            abi_type: AbiType::Tuple {
+                fields: vec![AbiType::Field, AbiType::Field],
+            },
+            visibility: AbiVisibility::Public,
+        });
+        let layout = WitnessLayout::new(&circuit).unwrap();
+        assert_eq!(
+            layout.public_outputs::<2>(&public_witness(8)).unwrap(),
+            [ark_bn254::Fr::from(4u64), ark_bn254::Fr::from(5u64)]
+        );
+    }
+
+    #[test]
+    fn reject_wrong_number_of_outputs() {
+        let layout = WitnessLayout::new(&circuit("monty_hall_init")).unwrap();
+        assert!(layout.public_outputs::<2>(&public_witness(8)).is_err());
+
+        let mut circuit = circuit("monty_hall_init");
+        circuit.abi.return_type = None;
+        let layout = WitnessLayout::new(&circuit).unwrap();
+        assert!(layout.public_outputs::<1>(&public_witness(8)).is_err());
+    }
+
+    #[test]
+    fn reject_short_witness() {
+        let layout = WitnessLayout::new(&circuit("monty_hall_init")).unwrap();
+        assert!(layout.public_outputs::<1>(&public_witness(4)).is_err());
+    }
+
+    #[test]
+    fn reject_shared_output() {
+        let layout = WitnessLayout::new(&circuit("monty_hall_init")).unwrap();
+        let mut witness = public_witness(8);
+        witness[4] = Rep3AcvmType::Shared(Rep3PrimeFieldShare::new(
+            ark_bn254::Fr::from(1u64),
+            ark_bn254::Fr::from(2u64),
+        ));
+        assert!(layout.public_outputs::<1>(&witness).is_err());
+    }
+
+    #[test]
+    fn reject_unknown_private_state() {
+        let layout = WitnessLayout::new(&circuit("taceo_commit")).unwrap();
+        assert!(
+            layout
+                .private_state(&public_witness(8), "unknown", PartyID::ID0)
+                .is_err()
+        );
+    }
+}
@@ -1 +1,6 @@
 pub mod monty_hall;
+
+/// The metadata key of the attempt id of a request running an MPC session.
+/// Every attempt of a step sends a fresh attempt id, the same to all nodes,
+/// such that a retry never joins the session of an aborted attempt.
+pub const SESSION_ATTEMPT_KEY: &str = "mpc-session-attempt";
@@ -1,13 +1,46 @@
 // This file is @generated by prost-build.
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
-pub struct SampleRandRequest {}
+pub struct GetPublicKeyRequest {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPublicKeyResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    pub public_key: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SampleRandRequest {
+    #[prost(string, tag = "1")]
+    pub game_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub request_id: ::prost::alloc::string::String,
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SampleRandResponse {
     #[prost(bytes = "vec", tag = "1")]
     pub seed_c: ::prost::alloc::vec::Vec<u8>,
 }
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
-pub struct InitGameRequest {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GenerateNullifyingKeyRequest {
+    #[prost(string, tag = "1")]
+    pub game_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub request_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GenerateNullifyingKeyResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    pub pk_x: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub pk_y: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bool, tag = "3")]
+    pub pk_is_infinite: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InitGameRequest {
+    #[prost(string, tag = "1")]
+    pub game_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub request_id: ::prost::alloc::string::String,
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct InitGameResponse {
     #[prost(bytes = "vec", tag = "1")]
@@ -15,10 +48,218 @@ pub struct InitGameResponse {
     #[prost(bytes = "vec", tag = "2")]
     pub game_state_c: ::prost::alloc::vec::Vec<u8>,
 }
+/// The player input is a crypto_box sealed box for this node, containing its
+/// share of the door together with its shares of the commitment randomness.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevealDoorRequest {
+    #[prost(string, tag = "1")]
+    pub game_id: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub door_ciphertext: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub door_c: ::prost::alloc::vec::Vec<u8>,
+    /// The crypto_box public key the node encrypts its share of the opened
+    /// door for.
+    #[prost(bytes = "vec", tag = "4")]
+    pub player_public_key: ::prost::alloc::vec::Vec<u8>,
+    #[prost(string, tag = "5")]
+    pub request_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevealDoorResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    pub proof: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub new_game_state_c: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub opened_door_c: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub game_state_nullifier: ::prost::alloc::vec::Vec<u8>,
+    /// A sealed box for the player, containing the node's share of the opened
+    /// door together with its share of the commitment randomness.
+    #[prost(bytes = "vec", tag = "5")]
+    pub opened_door_ciphertext: ::prost::alloc::vec::Vec<u8>,
+}
+/// The player input is a crypto_box sealed box for this node, containing its
+/// share of the switch decision together with its shares of the commitment
+/// randomness.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FinishGameRequest {
+    #[prost(string, tag = "1")]
+    pub game_id: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub switch_ciphertext: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub switch_c: ::prost::alloc::vec::Vec<u8>,
+    #[prost(string, tag = "4")]
+    pub request_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FinishGameResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    pub proof: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub win_c: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub game_state_nullifier: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RefreshSharesRequest {
+    #[prost(string, tag = "1")]
+    pub request_id: ::prost::alloc::string::String,
+}
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
-pub struct RevealDoorRequest {}
+pub struct RefreshSharesResponse {
+    #[prost(uint64, tag = "1")]
+    pub refreshed_games: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Job {
+    #[prost(string, tag = "1")]
+    pub game_id: ::prost::alloc::string::String,
+    #[prost(enumeration = "GamePhase", tag = "2")]
+    pub phase: i32,
+    #[prost(enumeration = "JobStatus", tag = "3")]
+    pub status: i32,
+    /// The public output of the step, the same on all nodes that completed
+    /// it. Empty while the job is running.
+    #[prost(bytes = "vec", tag = "4")]
+    pub output: ::prost::alloc::vec::Vec<u8>,
+    /// The seconds since the job changed its status, measured by the node.
+    #[prost(uint64, tag = "5")]
+    pub age_secs: u64,
+}
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
-pub struct RevealDoorResponse {}
+pub struct ListJobsRequest {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListJobsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub jobs: ::prost::alloc::vec::Vec<Job>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RollbackJobRequest {
+    #[prost(string, tag = "1")]
+    pub game_id: ::prost::alloc::string::String,
+    #[prost(enumeration = "GamePhase", tag = "2")]
+    pub phase: i32,
+    #[prost(string, tag = "3")]
+    pub request_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct RollbackJobResponse {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchJobRequest {
+    #[prost(string, tag = "1")]
+    pub game_id: ::prost::alloc::string::String,
+    #[prost(enumeration = "GamePhase", tag = "2")]
+    pub phase: i32,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct StageEvent {
+    #[prost(enumeration = "Stage", tag = "1")]
+    pub stage: i32,
+    /// How long the stage took on this node.
+    #[prost(uint64, tag = "2")]
+    pub duration_ms: u64,
+}
+/// The phase a game step moves its game to, in the order of the steps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum GamePhase {
+    Sampled = 0,
+    KeyGenerated = 1,
+    Started = 2,
+    OpenedDoor = 3,
+    Done = 4,
+}
+impl GamePhase {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Sampled => "SAMPLED",
+            Self::KeyGenerated => "KEY_GENERATED",
+            Self::Started => "STARTED",
+            Self::OpenedDoor => "OPENED_DOOR",
+            Self::Done => "DONE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SAMPLED" => Some(Self::Sampled),
+            "KEY_GENERATED" => Some(Self::KeyGenerated),
+            "STARTED" => Some(Self::Started),
+            "OPENED_DOOR" => Some(Self::OpenedDoor),
+            "DONE" => Some(Self::Done),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum JobStatus {
+    Running = 0,
+    Completed = 1,
+    RolledBack = 2,
+}
+impl JobStatus {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Running => "RUNNING",
+            Self::Completed => "COMPLETED",
+            Self::RolledBack => "ROLLED_BACK",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "RUNNING" => Some(Self::Running),
+            "COMPLETED" => Some(Self::Completed),
+            "ROLLED_BACK" => Some(Self::RolledBack),
+            _ => None,
+        }
+    }
+}
+/// The stages of a game step on a node, in the order they run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Stage {
+    WitnessExtension = 0,
+    ProvingKey = 1,
+    Proof = 2,
+    Verify = 3,
+}
+impl Stage {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::WitnessExtension => "WITNESS_EXTENSION",
+            Self::ProvingKey => "PROVING_KEY",
+            Self::Proof => "PROOF",
+            Self::Verify => "VERIFY",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "WITNESS_EXTENSION" => Some(Self::WitnessExtension),
+            "PROVING_KEY" => Some(Self::ProvingKey),
+            "PROOF" => Some(Self::Proof),
+            "VERIFY" => Some(Self::Verify),
+            _ => None,
+        }
+    }
+}
 /// Generated client implementations.
 pub mod mpc_node_service_client {
     #![allow(
@@ -30,6 +271,10 @@ pub mod mpc_node_service_client {
     )]
     use tonic::codegen::*;
     use tonic::codegen::http::Uri;
+    /// Every request changing the state of a node carries a request id chosen by
+    /// the caller. A node answers a repeated request with the response it stored
+    /// for the request id, without running the request again. A repeated request
+    /// arriving while the first one runs waits for its response.
     #[derive(Debug, Clone)]
     pub struct MpcNodeServiceClient<T> {
         inner: tonic::client::Grpc<T>,
@@ -110,6 +355,30 @@ pub mod mpc_node_service_client {
             self.inner = self.inner.max_encoding_message_size(limit);
             self
         }
+        pub async fn get_public_key(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetPublicKeyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetPublicKeyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/monty_hall.MpcNodeService/GetPublicKey",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("monty_hall.MpcNodeService", "GetPublicKey"));
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn sample_rand(
             &mut self,
             request: impl tonic::IntoRequest<super::SampleRandRequest>,
@@ -134,6 +403,32 @@ pub mod mpc_node_service_client {
                 .insert(GrpcMethod::new("monty_hall.MpcNodeService", "SampleRand"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn generate_nullifying_key(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GenerateNullifyingKeyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GenerateNullifyingKeyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/monty_hall.MpcNodeService/GenerateNullifyingKey",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("monty_hall.MpcNodeService", "GenerateNullifyingKey"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn init_game(
             &mut self,
             request: impl tonic::IntoRequest<super::InitGameRequest>,
@@ -182,6 +477,136 @@ pub mod mpc_node_service_client {
                 .insert(GrpcMethod::new("monty_hall.MpcNodeService", "RevealDoor"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn finish_game(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FinishGameRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::FinishGameResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/monty_hall.MpcNodeService/FinishGame",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("monty_hall.MpcNodeService", "FinishGame"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Admin: re-randomizes all stored shares of this node together with the
+        /// other nodes. Must be triggered on all nodes.
+        pub async fn refresh_shares(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RefreshSharesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RefreshSharesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/monty_hall.MpcNodeService/RefreshShares",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("monty_hall.MpcNodeService", "RefreshShares"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Admin: lists the jobs of the game steps this node is running or has
+        /// completed.
+        pub async fn list_jobs(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListJobsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListJobsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/monty_hall.MpcNodeService/ListJobs",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("monty_hall.MpcNodeService", "ListJobs"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Admin: undoes the last completed step of a game, e.g. because not all
+        /// nodes completed it.
+        pub async fn rollback_job(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RollbackJobRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RollbackJobResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/monty_hall.MpcNodeService/RollbackJob",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("monty_hall.MpcNodeService", "RollbackJob"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Streams an event per finished stage of the job of a game step while it
+        /// runs on this node. The stream waits for the job to start and ends with
+        /// the job. A step answered from its stored response runs no job, so the
+        /// caller stops watching once the step returned.
+        pub async fn watch_job(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WatchJobRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::StageEvent>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/monty_hall.MpcNodeService/WatchJob",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("monty_hall.MpcNodeService", "WatchJob"));
+            self.inner.server_streaming(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -197,6 +622,13 @@ pub mod mpc_node_service_server {
     /// Generated trait containing gRPC methods that should be implemented for use with MpcNodeServiceServer.
     #[async_trait]
     pub trait MpcNodeService: std::marker::Send + std::marker::Sync + 'static {
+        async fn get_public_key(
+            &self,
+            request: tonic::Request<super::GetPublicKeyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetPublicKeyResponse>,
+            tonic::Status,
+        >;
         async fn sample_rand(
             &self,
             request: tonic::Request<super::SampleRandRequest>,
@@ -204,6 +636,13 @@ pub mod mpc_node_service_server {
             tonic::Response<super::SampleRandResponse>,
             tonic::Status,
         >;
+        async fn generate_nullifying_key(
+            &self,
+            request: tonic::Request<super::GenerateNullifyingKeyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GenerateNullifyingKeyResponse>,
+            tonic::Status,
+        >;
         async fn init_game(
             &self,
             request: tonic::Request<super::InitGameRequest>,
@@ -218,7 +657,59 @@ pub mod mpc_node_service_server {
             tonic::Response<super::RevealDoorResponse>,
             tonic::Status,
         >;
+        async fn finish_game(
+            &self,
+            request: tonic::Request<super::FinishGameRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::FinishGameResponse>,
+            tonic::Status,
+        >;
+        /// Admin: re-randomizes all stored shares of this node together with the
+        /// other nodes. Must be triggered on all nodes.
+        async fn refresh_shares(
+            &self,
+            request: tonic::Request<super::RefreshSharesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RefreshSharesResponse>,
+            tonic::Status,
+        >;
+        /// Admin: lists the jobs of the game steps this node is running or has
+        /// completed.
+        async fn list_jobs(
+            &self,
+            request: tonic::Request<super::ListJobsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListJobsResponse>,
+            tonic::Status,
+        >;
+        /// Admin: undoes the last completed step of a game, e.g. because not all
+        /// nodes completed it.
+        async fn rollback_job(
+            &self,
+            request: tonic::Request<super::RollbackJobRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RollbackJobResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the WatchJob method.
+        type WatchJobStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::StageEvent, tonic::Status>,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Streams an event per finished stage of the job of a game step while it
+        /// runs on this node. The stream waits for the job to start and ends with
+        /// the job. A step answered from its stored response runs no job, so the
+        /// caller stops watching once the step returned.
+        async fn watch_job(
+            &self,
+            request: tonic::Request<super::WatchJobRequest>,
+        ) -> std::result::Result<tonic::Response<Self::WatchJobStream>, tonic::Status>;
     }
+    /// Every request changing the state of a node carries a request id chosen by
+    /// the caller. A node answers a repeated request with the response it stored
+    /// for the request id, without running the request again. A repeated request
+    /// arriving while the first one runs waits for its response.
     #[derive(Debug)]
     pub struct MpcNodeServiceServer<T> {
         inner: Arc<T>,
@@ -295,6 +786,51 @@ pub mod mpc_node_service_server {
         }
         fn call(&mut self, req: http::Request<B>) -> Self::Future {
             match req.uri().path() {
+                "/monty_hall.MpcNodeService/GetPublicKey" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetPublicKeySvc<T: MpcNodeService>(pub Arc<T>);
+                    impl<
+                        T: MpcNodeService,
+                    > tonic::server::UnaryService<super::GetPublicKeyRequest>
+                    for GetPublicKeySvc<T> {
+                        type Response = super::GetPublicKeyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetPublicKeyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as MpcNodeService>::get_public_key(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetPublicKeySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/monty_hall.MpcNodeService/SampleRand" => {
                     #[allow(non_camel_case_types)]
                     struct SampleRandSvc<T: MpcNodeService>(pub Arc<T>);
@@ -340,6 +876,55 @@ pub mod mpc_node_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/monty_hall.MpcNodeService/GenerateNullifyingKey" => {
+                    #[allow(non_camel_case_types)]
+                    struct GenerateNullifyingKeySvc<T: MpcNodeService>(pub Arc<T>);
+                    impl<
+                        T: MpcNodeService,
+                    > tonic::server::UnaryService<super::GenerateNullifyingKeyRequest>
+                    for GenerateNullifyingKeySvc<T> {
+                        type Response = super::GenerateNullifyingKeyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GenerateNullifyingKeyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as MpcNodeService>::generate_nullifying_key(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GenerateNullifyingKeySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/monty_hall.MpcNodeService/InitGame" => {
                     #[allow(non_camel_case_types)]
                     struct InitGameSvc<T: MpcNodeService>(pub Arc<T>);
@@ -430,6 +1015,232 @@ pub mod mpc_node_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/monty_hall.MpcNodeService/FinishGame" => {
+                    #[allow(non_camel_case_types)]
+                    struct FinishGameSvc<T: MpcNodeService>(pub Arc<T>);
+                    impl<
+                        T: MpcNodeService,
+                    > tonic::server::UnaryService<super::FinishGameRequest>
+                    for FinishGameSvc<T> {
+                        type Response = super::FinishGameResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::FinishGameRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as MpcNodeService>::finish_game(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = FinishGameSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/monty_hall.MpcNodeService/RefreshShares" => {
+                    #[allow(non_camel_case_types)]
+                    struct RefreshSharesSvc<T: MpcNodeService>(pub Arc<T>);
+                    impl<
+                        T: MpcNodeService,
+                    > tonic::server::UnaryService<super::RefreshSharesRequest>
+                    for RefreshSharesSvc<T> {
+                        type Response = super::RefreshSharesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RefreshSharesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as MpcNodeService>::refresh_shares(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RefreshSharesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/monty_hall.MpcNodeService/ListJobs" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListJobsSvc<T: MpcNodeService>(pub Arc<T>);
+                    impl<
+                        T: MpcNodeService,
+                    > tonic::server::UnaryService<super::ListJobsRequest>
+                    for ListJobsSvc<T> {
+                        type Response = super::ListJobsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListJobsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as MpcNodeService>::list_jobs(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListJobsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/monty_hall.MpcNodeService/RollbackJob" => {
+                    #[allow(non_camel_case_types)]
+                    struct RollbackJobSvc<T: MpcNodeService>(pub Arc<T>);
+                    impl<
+                        T: MpcNodeService,
+                    > tonic::server::UnaryService<super::RollbackJobRequest>
+                    for RollbackJobSvc<T> {
+                        type Response = super::RollbackJobResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RollbackJobRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as MpcNodeService>::rollback_job(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RollbackJobSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/monty_hall.MpcNodeService/WatchJob" => {
+                    #[allow(non_camel_case_types)]
+                    struct WatchJobSvc<T: MpcNodeService>(pub Arc<T>);
+                    impl<
+                        T: MpcNodeService,
+                    > tonic::server::ServerStreamingService<super::WatchJobRequest>
+                    for WatchJobSvc<T> {
+                        type Response = super::StageEvent;
+                        type ResponseStream = T::WatchJobStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WatchJobRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as MpcNodeService>::watch_job(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = WatchJobSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(empty_body());
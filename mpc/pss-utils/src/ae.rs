@@ -0,0 +1,81 @@
+//! The authenticated encryption of `pss_utils::ae`, a duplex sponge
+//! following the SAFE API (<https://eprint.iacr.org/2023/522.pdf>).
+
+use ark_ff::{MontFp, Zero as _};
+
+use crate::{F, permutation};
+
+/// Absorb 2, squeeze 1, absorb 1, squeeze 1, domainsep = 0x4142, i.e.,
+/// `0x800000020000000180000001000000014142`.
+const T1: F = MontFp!("11150372609649905289650903123088592150282562");
+/// Absorb 2, squeeze 2, absorb 2, squeeze 1, domainsep = 0x4142, i.e.,
+/// `0x800000020000000280000002000000014142`.
+const T2: F = MontFp!("11150372609649905290859828942984696301699394");
+/// Absorb 2, squeeze 3, absorb 3, squeeze 1, domainsep = 0x4142, i.e.,
+/// `0x800000020000000380000003000000014142`.
+const T3: F = MontFp!("11150372609649905292068754762880800453116226");
+
+fn init(key: F, nonce: F, tag: F) -> [F; 4] {
+    permutation(&[key, nonce, F::zero(), tag])
+}
+
+fn encrypt<const N: usize>(mut data: [F; N], key: F, nonce: F, t: F) -> ([F; N], F) {
+    let mut state = init(key, nonce, t);
+    for (state, data) in state.iter_mut().zip(data.iter_mut()) {
+        *state += *data;
+        *data = *state;
+    }
+    let tag = permutation(&state)[0];
+    (data, tag)
+}
+
+fn decrypt<const N: usize>(
+    mut ciphertext: [F; N],
+    tag: F,
+    key: F,
+    nonce: F,
+    t: F,
+) -> eyre::Result<[F; N]> {
+    let mut state = init(key, nonce, t);
+    for (state, ciphertext) in state.iter_mut().zip(ciphertext.iter_mut()) {
+        let plaintext = *ciphertext - *state;
+        *state = *ciphertext;
+        *ciphertext = plaintext;
+    }
+    if permutation(&state)[0] != tag {
+        eyre::bail!("invalid authentication tag");
+    }
+    Ok(ciphertext)
+}
+
+/// `AE::encrypt1`. Returns the ciphertext and the tag.
+pub fn encrypt1(data: F, key: F, nonce: F) -> (F, F) {
+    let ([ciphertext], tag) = encrypt([data], key, nonce, T1);
+    (ciphertext, tag)
+}
+
+/// `AE::decrypt1`. Fails if the tag does not match.
+pub fn decrypt1(ciphertext: F, tag: F, key: F, nonce: F) -> eyre::Result<F> {
+    let [plaintext] = decrypt([ciphertext], tag, key, nonce, T1)?;
+    Ok(plaintext)
+}
+
+/// `AE::encrypt2`. Returns the ciphertext and the tag.
+pub fn encrypt2(data: [F; 2], key: F, nonce: F) -> ([F; 2], F) {
+    encrypt(data, key, nonce, T2)
+}
+
+/// `AE::decrypt2`. Fails if the tag does not match.
+pub fn decrypt2(ciphertext: [F; 2], tag: F, key: F, nonce: F) -> eyre::Result<[F; 2]> {
+    decrypt(ciphertext, tag, key, nonce, T2)
+}
+
+/// `AE::encrypt3`. Returns the ciphertext and the tag.
+pub fn encrypt3(data: [F; 3], key: F, nonce: F) -> ([F; 3], F) {
+    encrypt(data, key, nonce, T3)
+}
+
+/// `AE::decrypt3`. Fails if the tag does not match.
+pub fn decrypt3(ciphertext: [F; 3], tag: F, key: F, nonce: F) -> eyre::Result<[F; 3]> {
+    decrypt(ciphertext, tag, key, nonce, T3)
+}
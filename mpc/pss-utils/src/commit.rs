@@ -0,0 +1,50 @@
+//! `pss_utils::commit`.
+
+use ark_ff::Zero as _;
+
+use crate::{F, permutation};
+
+/// The domain separator of the SAFE sponge.
+const DOMAIN_SEPARATOR: u64 = 0x4142;
+
+/// Noir's `Poseidon2::hash`, i.e., `Commit::poseidon2_hash`. This is also
+/// Aztec's `poseidon2Hash`.
+pub fn poseidon2_hash(inputs: &[F]) -> F {
+    const RATE: usize = 3;
+    let iv = F::from(inputs.len() as u64) * F::from(1u128 << 64);
+    let mut state = [F::zero(); 4];
+    state[RATE] = iv;
+    for chunk in inputs.chunks(RATE) {
+        for (state, input) in state.iter_mut().zip(chunk) {
+            *state += input;
+        }
+        state = permutation(&state);
+    }
+    if inputs.is_empty() {
+        state = permutation(&state);
+    }
+    state[0]
+}
+
+/// `Commit::commit_field_array`: absorbs `data` into a sponge following the
+/// SAFE API (<https://eprint.iacr.org/2023/522.pdf>) and squeezes a single
+/// field element.
+pub fn commit_field_array(data: &[F]) -> F {
+    // IO pattern: absorb N, squeeze 1
+    let absorb = F::from(0x8000_0000u64 + data.len() as u64);
+    let squeeze = F::from(1u64);
+    let tag =
+        F::from(DOMAIN_SEPARATOR) + squeeze * F::from(1u64 << 16) + absorb * F::from(1u64 << 48);
+
+    let mut state = [F::zero(), F::zero(), F::zero(), tag];
+    let mut position = 0;
+    for input in data {
+        if position == 3 {
+            state = permutation(&state);
+            position = 0;
+        }
+        state[position] += input;
+        position += 1;
+    }
+    permutation(&state)[0]
+}
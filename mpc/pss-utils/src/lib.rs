@@ -0,0 +1,21 @@
+//! Native implementations of the primitives of the `pss_utils` Noir library
+//! in `noir_logic`.
+//!
+//! The functions mirror their Noir counterparts one to one and have to stay
+//! in sync with them, as off-circuit code (the orchestration, the player)
+//! recomputes what the circuits compute. The tests check them against the
+//! `Prover.toml` fixtures of the circuits.
+
+pub mod ae;
+pub mod commit;
+pub mod private_state;
+
+pub use commit::{commit_field_array, poseidon2_hash};
+pub use private_state::{commit, compute_nullifier};
+
+type F = ark_bn254::Fr;
+
+/// `std::hash::poseidon2_permutation(state, 4)`.
+fn permutation(state: &[F; 4]) -> [F; 4] {
+    mpc_core::gadgets::poseidon2::Poseidon2::<F, 4, 5>::default().permutation(state)
+}
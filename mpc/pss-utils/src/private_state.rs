@@ -0,0 +1,29 @@
+//! The commitments and nullifiers of `pss_utils::private_state`.
+
+use crate::{F, commit::poseidon2_hash};
+
+/// `GENERATOR_INDEX__NOTE_HASH` of the Aztec protocol constants.
+pub const GENERATOR_INDEX_NOTE_HASH: u64 = 1;
+/// `GENERATOR_INDEX__NOTE_NULLIFIER` of the Aztec protocol constants.
+pub const GENERATOR_INDEX_NOTE_NULLIFIER: u64 = 53;
+
+/// `PrivateState::commit`: the Aztec note hash of `data` (as produced by
+/// `as_field_array`) with the `CommitMetaData` in place of owner and storage
+/// slot.
+pub fn commit(data: &[F], meta: &[F]) -> F {
+    let mut packed = Vec::with_capacity(1 + data.len() + meta.len());
+    packed.push(F::from(GENERATOR_INDEX_NOTE_HASH));
+    packed.extend_from_slice(data);
+    packed.extend_from_slice(meta);
+    poseidon2_hash(&packed)
+}
+
+/// `PrivateState::compute_nullifier`: the Aztec note nullifier of a
+/// commitment under `secret_key`.
+pub fn compute_nullifier(commitment: F, secret_key: F) -> F {
+    poseidon2_hash(&[
+        F::from(GENERATOR_INDEX_NOTE_NULLIFIER),
+        commitment,
+        secret_key,
+    ])
+}
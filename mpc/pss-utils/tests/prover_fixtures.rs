@@ -0,0 +1,241 @@
+//! Checks the native primitives against the inputs of the circuits in
+//! `noir_logic`. The `Prover.toml` fixtures satisfy the circuits, so every
+//! public commitment in them has to match what we compute off-circuit.
+//! Outputs that are not part of a fixture are checked against fixed vectors
+//! that the tests of `pss_utils` check against the Noir implementation.
+
+use std::path::PathBuf;
+use std::str::FromStr as _;
+
+use ark_bn254::Fr;
+use serde::Deserialize;
+
+fn read_fixture<T: serde::de::DeserializeOwned>(circuit: &str) -> T {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../noir_logic")
+        .join(circuit)
+        .join("Prover.toml");
+    let fixture = std::fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("cannot read {}: {err}", path.display()));
+    toml::from_str(&fixture).unwrap_or_else(|err| panic!("invalid {}: {err}", path.display()))
+}
+
+fn field(value: &str) -> Fr {
+    Fr::from_str(value).unwrap_or_else(|_| panic!("{value} is not a field element"))
+}
+
+fn fields(values: &[String]) -> Vec<Fr> {
+    values.iter().map(|value| field(value)).collect()
+}
+
+#[derive(Deserialize)]
+struct CommitMetaData {
+    data: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Prng {
+    state: [String; 4],
+    position: String,
+}
+
+#[derive(Deserialize)]
+struct GameState {
+    state: String,
+    prng: Prng,
+    winning_door: String,
+    chosen_door: String,
+    open_door: String,
+}
+
+impl GameState {
+    /// `GameState::as_field_array`.
+    fn as_field_array(&self) -> Vec<Fr> {
+        let mut fields = vec![field(&self.state)];
+        fields.extend(self.prng.state.iter().map(|value| field(value)));
+        fields.push(field(&self.prng.position));
+        fields.push(field(&self.winning_door));
+        fields.push(field(&self.chosen_door));
+        fields.push(field(&self.open_door));
+        fields
+    }
+}
+
+#[derive(Deserialize)]
+struct Init {
+    seed: String,
+    seed_c: String,
+    seed_r: CommitMetaData,
+}
+
+#[derive(Deserialize)]
+struct Choose {
+    state: GameState,
+    state_r: CommitMetaData,
+    state_c: String,
+    door: String,
+    door_r: CommitMetaData,
+    door_c: String,
+}
+
+#[derive(Deserialize)]
+struct Switch {
+    state: GameState,
+    state_r: CommitMetaData,
+    state_c: String,
+    switch: String,
+    switch_r: CommitMetaData,
+    switch_c: String,
+    nullifying_key: String,
+}
+
+#[test]
+fn init_seed_commitment() {
+    let fixture = read_fixture::<Init>("monty_hall_init");
+    assert_eq!(
+        pss_utils::commit(&[field(&fixture.seed)], &fields(&fixture.seed_r.data)),
+        field(&fixture.seed_c)
+    );
+}
+
+#[test]
+fn choose_commitments() {
+    let fixture = read_fixture::<Choose>("monty_hall_choose");
+    assert_eq!(
+        pss_utils::commit(
+            &fixture.state.as_field_array(),
+            &fields(&fixture.state_r.data)
+        ),
+        field(&fixture.state_c)
+    );
+    assert_eq!(
+        pss_utils::commit(&[field(&fixture.door)], &fields(&fixture.door_r.data)),
+        field(&fixture.door_c)
+    );
+}
+
+#[test]
+fn switch_commitments() {
+    let fixture = read_fixture::<Switch>("monty_hall_switch");
+    assert_eq!(
+        pss_utils::commit(
+            &fixture.state.as_field_array(),
+            &fields(&fixture.state_r.data)
+        ),
+        field(&fixture.state_c)
+    );
+    assert_eq!(
+        pss_utils::commit(&[field(&fixture.switch)], &fields(&fixture.switch_r.data)),
+        field(&fixture.switch_c)
+    );
+}
+
+#[test]
+fn commit_field_array_depends_on_length() {
+    // the length is part of the IO pattern, so padding with zeros must change
+    // the commitment
+    let data = [Fr::from(1u64), Fr::from(2u64), Fr::from(3u64)];
+    assert_ne!(
+        pss_utils::commit_field_array(&data),
+        pss_utils::commit_field_array(&[data[0], data[1], data[2], Fr::from(0u64)])
+    );
+}
+
+#[test]
+fn commit_field_array_vectors() {
+    // the same vectors as `commit_field_array_vector` in
+    // `pss_utils/src/commit.nr`
+    let data = [
+        Fr::from(1u64),
+        Fr::from(2u64),
+        Fr::from(3u64),
+        Fr::from(4u64),
+    ];
+    assert_eq!(
+        pss_utils::commit_field_array(&data[..3]),
+        field("9694327389039095237979009727531391827141984371280052992339936354758943474912")
+    );
+    assert_eq!(
+        pss_utils::commit_field_array(&data),
+        field("18311472217401278258888468982459381085619927649099115791363253298569313686245")
+    );
+}
+
+#[test]
+fn switch_nullifier() {
+    // the same vector as `compute_nullifier_vector` in
+    // `pss_utils/src/private_state.nr`
+    let fixture = read_fixture::<Switch>("monty_hall_switch");
+    assert_eq!(
+        pss_utils::compute_nullifier(field(&fixture.state_c), field(&fixture.nullifying_key)),
+        field("14250858933867568231446590812608755465951847822300019871047197506294088938969")
+    );
+}
+
+// The same cases as the tests in `pss_utils/src/ae.nr`.
+
+#[test]
+fn encrypt_decrypt1() {
+    let (data, key, nonce) = (Fr::from(0x123u64), Fr::from(0x456u64), Fr::from(0x789u64));
+    let (ciphertext, tag) = pss_utils::ae::encrypt1(data, key, nonce);
+    assert_eq!(
+        ciphertext,
+        field("18283513848643738060978946021583202612015214101018081408373402841495009102265")
+    );
+    assert_eq!(
+        tag,
+        field("9156627760360418551526578244420858780698188219945227075419234240700652031476")
+    );
+    assert_eq!(
+        pss_utils::ae::decrypt1(ciphertext, tag, key, nonce).unwrap(),
+        data
+    );
+    assert!(pss_utils::ae::decrypt1(ciphertext, tag, key, nonce + Fr::from(1u64)).is_err());
+}
+
+#[test]
+fn encrypt_decrypt2() {
+    let data = [Fr::from(0x123u64), Fr::from(0x456u64)];
+    let (key, nonce) = (Fr::from(0x789u64), Fr::from(0xabcu64));
+    let (ciphertext, tag) = pss_utils::ae::encrypt2(data, key, nonce);
+    assert_eq!(
+        ciphertext,
+        [
+            field("3337711250464522075522818319628424543284761718543270849015741370318686006032"),
+            field("15922647791639319615714288778951127658632241210747879301742201109396621992712")
+        ]
+    );
+    assert_eq!(
+        tag,
+        field("19502284085804646108745042379960249450644534573693693072517136702600504335262")
+    );
+    assert_eq!(
+        pss_utils::ae::decrypt2(ciphertext, tag, key, nonce).unwrap(),
+        data
+    );
+    assert!(pss_utils::ae::decrypt2(ciphertext, tag, key, nonce + Fr::from(1u64)).is_err());
+}
+
+#[test]
+fn encrypt_decrypt3() {
+    let data = [Fr::from(0x123u64), Fr::from(0x456u64), Fr::from(0x789u64)];
+    let (key, nonce) = (Fr::from(0xabcu64), Fr::from(0xdefu64));
+    let (ciphertext, tag) = pss_utils::ae::encrypt3(data, key, nonce);
+    assert_eq!(
+        ciphertext,
+        [
+            field("11077458319930763158264261752186818455267565934677636075239407461017450546511"),
+            field("8953368484039771167338963580927382827340497226514813446520285556243471529069"),
+            field("16283493956694370909304266167599160473616270432958426532535331207511223843269")
+        ]
+    );
+    assert_eq!(
+        tag,
+        field("14988717382224902922682694763612926982752483614757402798242528350266386141011")
+    );
+    assert_eq!(
+        pss_utils::ae::decrypt3(ciphertext, tag, key, nonce).unwrap(),
+        data
+    );
+    assert!(pss_utils::ae::decrypt3(ciphertext, tag, key, nonce + Fr::from(1u64)).is_err());
+}